@@ -2,8 +2,8 @@ use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CoordsDto {
-    pub x: usize,
-    pub y: usize,
+    pub x: isize,
+    pub y: isize,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -45,6 +45,8 @@ pub struct FieldDto {
     pub cells: Vec<Vec<CellDto>>,
     pub width: usize,
     pub height: usize,
+    #[serde(rename = "lastMove", default, skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<CoordsDto>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,6 +55,8 @@ pub struct GameSettingsDto {
     pub height: usize, 
     #[serde(rename = "winCondition")]
     pub win_condition: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -61,6 +65,13 @@ pub enum StateDto {
     XMove,
     #[serde(rename = "O_MOVE")]
     OMove,
+    /// Opening rules: X places the opening stones.
+    #[serde(rename = "OPENING")]
+    Opening,
+    #[serde(rename = "X_SIDE_CHOICE")]
+    XSideChoice,
+    #[serde(rename = "O_SIDE_CHOICE")]
+    OSideChoice,
     #[serde(rename = "FINISHED")]
     Finished,
 }
@@ -77,6 +88,8 @@ pub enum ResultDto {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GameDto {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub field: FieldDto,
     pub settings: GameSettingsDto,
     pub state: StateDto,
@@ -91,4 +104,18 @@ pub struct GameDto {
 pub struct TurnDto {
     pub side: SideDto,
     pub coords: CoordsDto,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OpeningDto {
+    pub stones: Vec<CoordsDto>,
+}
+
+/// Either a side to take or, in Swap2, stones to add.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SideChoiceDto {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<SideDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stones: Vec<CoordsDto>,
+}
@@ -0,0 +1,171 @@
+use crate::common::{
+    bot::{Coords, Field, Side, Size, Turn},
+    error::CoreError,
+    opening::SideChoice,
+};
+
+use super::dto::{CellDto, CoordsDto, FieldDto, SideChoiceDto, SideDto, TurnDto};
+
+pub trait ToDto<Dto> {
+    fn to_dto(&self) -> Dto;
+}
+
+pub trait ToEntity<Entity> {
+    fn to_entity(&self) -> Entity;
+}
+
+pub trait TryToEntity<Entity> {
+    fn try_to_entity(&self) -> Result<Entity, CoreError>;
+}
+
+impl ToEntity<Side> for SideDto {
+    fn to_entity(&self) -> Side {
+        match self {
+            SideDto::X => Side::X,
+            SideDto::O => Side::O,
+        }
+    }
+}
+
+impl ToDto<SideDto> for Side {
+    fn to_dto(&self) -> SideDto {
+        match self {
+            Side::X => SideDto::X,
+            Side::O => SideDto::O,
+        }
+    }
+}
+
+impl ToEntity<Coords> for CoordsDto {
+    fn to_entity(&self) -> Coords {
+        Coords {
+            x: self.x,
+            y: self.y,
+        }
+    }
+}
+
+impl ToDto<CoordsDto> for Coords {
+    fn to_dto(&self) -> CoordsDto {
+        CoordsDto {
+            x: self.x,
+            y: self.y,
+        }
+    }
+}
+
+impl ToEntity<Turn> for TurnDto {
+    fn to_entity(&self) -> Turn {
+        Turn {
+            coords: self.coords.to_entity(),
+            side: self.side.to_entity(),
+        }
+    }
+}
+
+impl ToDto<TurnDto> for Turn {
+    fn to_dto(&self) -> TurnDto {
+        TurnDto {
+            coords: self.coords.to_dto(),
+            side: self.side.to_dto(),
+        }
+    }
+}
+
+impl TryToEntity<Field> for FieldDto {
+    fn try_to_entity(&self) -> Result<Field, CoreError> {
+        let size = Size {
+            width: self.width,
+            height: self.height,
+        };
+        if self.cells.len() != size.height || self.cells.iter().any(|row| row.len() != size.width) {
+            return Err(CoreError::of_field_cells(size));
+        }
+
+        let mut stones = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let side = match cell {
+                    CellDto::X => Side::X,
+                    CellDto::O => Side::O,
+                    CellDto::Empty => continue,
+                };
+                let coords = Coords {
+                    x: x as isize,
+                    y: y as isize,
+                };
+                stones.push(Turn { coords, side });
+            }
+        }
+        let last_move = self.last_move.as_ref().map(|coords| coords.to_entity());
+
+        Field::new(size).with_stones(&stones, last_move)
+    }
+}
+
+impl ToEntity<SideChoice> for SideChoiceDto {
+    fn to_entity(&self) -> SideChoice {
+        match &self.side {
+            Some(side) => SideChoice::Take(side.to_entity()),
+            None => SideChoice::AddStones(self.stones.iter().map(|c| c.to_entity()).collect()),
+        }
+    }
+}
+
+impl ToDto<SideChoiceDto> for SideChoice {
+    fn to_dto(&self) -> SideChoiceDto {
+        match self {
+            SideChoice::Take(side) => SideChoiceDto {
+                side: Some(side.to_dto()),
+                stones: vec![],
+            },
+            SideChoice::AddStones(stones) => SideChoiceDto {
+                side: None,
+                stones: stones.iter().map(|coords| coords.to_dto()).collect(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api_client::dto::FieldDto,
+        common::bot::{Cell, Coords, Side},
+    };
+
+    use super::TryToEntity;
+
+    fn field_dto(cells: &str) -> FieldDto {
+        let json = format!(r#"{{"cells":{cells},"width":2,"height":2}}"#);
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn maps_field_cells_to_stones() {
+        let field = field_dto(r#"[["X","EMPTY"],["EMPTY","O"]]"#)
+            .try_to_entity()
+            .unwrap();
+
+        assert!(matches!(
+            field.get(Coords { x: 0, y: 0 }),
+            Some(Cell::Value(Side::X))
+        ));
+        assert!(matches!(
+            field.get(Coords { x: 1, y: 1 }),
+            Some(Cell::Value(Side::O))
+        ));
+        assert_eq!(field.stones().len(), 2);
+    }
+
+    #[test]
+    fn rejects_cells_not_matching_size() {
+        let short = r#"[["X","EMPTY"]]"#;
+        let narrow = r#"[["X"],["EMPTY","O"]]"#;
+        let wide = r#"[["X","O","X"],["O","X"]]"#;
+
+        for cells in [short, narrow, wide] {
+            assert!(field_dto(cells).try_to_entity().is_err(), "{cells}");
+        }
+    }
+}
@@ -1,65 +1,187 @@
-use std::time::Duration;
+use std::{
+    io::{self, ErrorKind},
+    thread,
+    time::{Duration, Instant},
+};
 
 use const_format::formatcp;
-use ureq::Error;
+use serde::de::DeserializeOwned;
+use ureq::{Error, Response};
 
-use super::dto::{CoordsDto, GameDto, PlayerDto, RegisterInfoDto};
+use crate::metrics::metrics;
 
-static MATCHMAKING_QUEUE: &str =
-    formatcp!("http://localhost:8080/api/v1/matchmaking/queue");
-static PLAYERS: &str =
-    formatcp!("http://localhost:8080/api/v1/players");
+use super::dto::{CoordsDto, GameDto, OpeningDto, PlayerDto, RegisterInfoDto, SideChoiceDto};
 
-pub fn post_mathchmaking_queue(body: RegisterInfoDto) -> Result<PlayerDto, Error> {
-    let res = ureq::post(MATCHMAKING_QUEUE)
-        .set("Content-Type", "application/json")
-        .send_string(&serde_json::to_string(&body).unwrap())?;
+static API: &str = "/api/v1";
+static MATCHMAKING_QUEUE: &str = formatcp!("{API}/matchmaking/queue");
+static PLAYERS: &str = formatcp!("{API}/players");
+static MAX_RETRIES: u32 = 3;
+static RETRY_DELAY: Duration = Duration::from_millis(200);
 
-    let ans = &res.into_string()?;
-    let ans: PlayerDto = serde_json::from_str(ans).unwrap();
+pub fn post_mathchmaking_queue(
+    server: &str,
+    timeout: Duration,
+    body: RegisterInfoDto,
+) -> Result<PlayerDto, Box<Error>> {
+    let res = send(formatcp!("POST {MATCHMAKING_QUEUE}"), false, || {
+        ureq::post(&format!("{server}{MATCHMAKING_QUEUE}"))
+            .timeout(timeout)
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(&body).unwrap())
+            .map_err(Box::new)
+    })?;
 
-    Ok(ans)
+    parse_response(res)
 }
 
 pub fn get_players(
+    server: &str,
+    timeout: Duration,
     id: &str,
     long_polling_enabled: bool,
     long_polling_timeout: usize,
-) -> Result<PlayerDto, Error> {
-    let res = ureq::get(&format!("{PLAYERS}/{id}"))
-        .timeout(Duration::from_secs(3600))
-        .query("longPollingEnabled", &long_polling_enabled.to_string())
-        .query("longPollingTimeout", &long_polling_timeout.to_string())
-        .set("Content-Type", "application/json")
-        .call()?;
-
-    let ans = &res.into_string()?;
-    let ans: PlayerDto = serde_json::from_str(ans).unwrap();
+) -> Result<PlayerDto, Box<Error>> {
+    let res = send(formatcp!("GET {PLAYERS}/{{id}}"), true, || {
+        ureq::get(&format!("{server}{PLAYERS}/{id}"))
+            .timeout(timeout)
+            .query("longPollingEnabled", &long_polling_enabled.to_string())
+            .query("longPollingTimeout", &long_polling_timeout.to_string())
+            .set("Content-Type", "application/json")
+            .call()
+            .map_err(Box::new)
+    })?;
 
-    Ok(ans)
+    parse_response(res)
 }
 
 pub fn get_player_game(
+    server: &str,
+    timeout: Duration,
     id: &str,
     long_polling_enabled: bool,
     long_polling_timeout: usize,
-) -> Result<GameDto, Error> {
-    let res = ureq::get(&format!("{PLAYERS}/{id}/game"))
-        .timeout(Duration::from_secs(3600))
-        .query("longPollingEnabled", &long_polling_enabled.to_string())
-        .query("longPollingTimeout", &long_polling_timeout.to_string())
-        .set("Content-Type", "application/json")
-        .call()?;
-    let ans = &res.into_string()?;
-    let ans: GameDto = serde_json::from_str(ans).unwrap();
+) -> Result<GameDto, Box<Error>> {
+    let res = send(formatcp!("GET {PLAYERS}/{{id}}/game"), true, || {
+        ureq::get(&format!("{server}{PLAYERS}/{id}/game"))
+            .timeout(timeout)
+            .query("longPollingEnabled", &long_polling_enabled.to_string())
+            .query("longPollingTimeout", &long_polling_timeout.to_string())
+            .set("Content-Type", "application/json")
+            .call()
+            .map_err(Box::new)
+    })?;
 
-    Ok(ans)
+    parse_response(res)
+}
+
+pub fn post_game_turn(
+    server: &str,
+    timeout: Duration,
+    id: &str,
+    body: CoordsDto,
+) -> Result<(), Box<Error>> {
+    send(formatcp!("POST {PLAYERS}/{{id}}/game/turn"), false, || {
+        ureq::post(&format!("{server}{PLAYERS}/{id}/game/turn"))
+            .timeout(timeout)
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(&body).unwrap())
+            .map_err(Box::new)
+    })?;
+
+    Ok(())
 }
 
-pub fn post_game_turn(id: &str, body: CoordsDto) -> Result<(), Error> {
-    let _ = ureq::post(&format!("{PLAYERS}/{id}/game/turn"))
-        .set("Content-Type", "application/json")
-        .send_string(&serde_json::to_string(&body).unwrap());
+pub fn post_game_opening(
+    server: &str,
+    timeout: Duration,
+    id: &str,
+    body: OpeningDto,
+) -> Result<(), Box<Error>> {
+    send(
+        formatcp!("POST {PLAYERS}/{{id}}/game/opening"),
+        false,
+        || {
+            ureq::post(&format!("{server}{PLAYERS}/{id}/game/opening"))
+                .timeout(timeout)
+                .set("Content-Type", "application/json")
+                .send_string(&serde_json::to_string(&body).unwrap())
+                .map_err(Box::new)
+        },
+    )?;
 
     Ok(())
 }
+
+pub fn post_game_side(
+    server: &str,
+    timeout: Duration,
+    id: &str,
+    body: SideChoiceDto,
+) -> Result<(), Box<Error>> {
+    send(formatcp!("POST {PLAYERS}/{{id}}/game/side"), false, || {
+        ureq::post(&format!("{server}{PLAYERS}/{id}/game/side"))
+            .timeout(timeout)
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(&body).unwrap())
+            .map_err(Box::new)
+    })?;
+
+    Ok(())
+}
+
+/// Sends a request, recording its latency and outcome under `endpoint`.
+/// Idempotent requests are `retried` on transport errors other than timeouts and on 5xx
+/// statuses: a server that is too slow to answer would only keep the caller waiting longer.
+fn send(
+    endpoint: &str,
+    retried: bool,
+    request: impl Fn() -> Result<Response, Box<Error>>,
+) -> Result<Response, Box<Error>> {
+    let metrics = metrics();
+    let mut attempt = 0;
+
+    loop {
+        let started = Instant::now();
+        let res = request();
+        metrics
+            .http_latency
+            .observe(&[("endpoint", endpoint)], started.elapsed().as_secs_f64());
+
+        let (status, transient) = match &res {
+            Ok(res) => (res.status().to_string(), false),
+            Err(e) => match e.as_ref() {
+                Error::Status(code, _) => (code.to_string(), *code >= 500),
+                Error::Transport(_) => ("transport".to_owned(), !is_timeout(e)),
+            },
+        };
+        metrics
+            .http_requests
+            .inc(&[("endpoint", endpoint), ("status", &status)]);
+        if res.is_err() {
+            metrics.http_errors.inc(&[("endpoint", endpoint)]);
+        }
+
+        if retried && transient && attempt < MAX_RETRIES {
+            attempt += 1;
+            metrics.http_retries.inc(&[("endpoint", endpoint)]);
+            thread::sleep(RETRY_DELAY * attempt);
+            continue;
+        }
+
+        return res;
+    }
+}
+
+fn is_timeout(e: &Error) -> bool {
+    std::error::Error::source(e)
+        .and_then(|source| source.downcast_ref::<io::Error>())
+        .is_some_and(|source| source.kind() == ErrorKind::TimedOut)
+}
+
+fn parse_response<T: DeserializeOwned>(res: Response) -> Result<T, Box<Error>> {
+    let ans = &res.into_string().map_err(Error::from)?;
+    let ans = serde_json::from_str(ans)
+        .map_err(|e| Error::from(io::Error::new(ErrorKind::InvalidData, e)))?;
+
+    Ok(ans)
+}
@@ -1,2 +1,3 @@
 pub mod dto;
-pub mod methods;
\ No newline at end of file
+pub mod mapping;
+pub mod methods;
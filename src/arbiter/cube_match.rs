@@ -0,0 +1,143 @@
+use crate::common::{
+    bot::Side,
+    cube::{Cube, CubeBot, Turn3},
+    cube_rules::CubeRules,
+};
+
+use super::local_match::{GameResult, MatchScore};
+
+pub struct CubeOutcome {
+    pub winner: Option<Side>,
+    pub turns: Vec<Turn3>,
+    pub forfeit: Option<String>,
+}
+
+/// Plays a Qubic game on a cube of `size` cells a side, `x` moving first.
+pub fn play_game(x: &dyn CubeBot, o: &dyn CubeBot, size: usize) -> CubeOutcome {
+    let rules = CubeRules::new(size);
+    let mut cube = Cube::new(size);
+    let mut outcome = CubeOutcome {
+        winner: None,
+        turns: vec![],
+        forfeit: None,
+    };
+
+    let played = play_turns(x, o, &rules, &mut cube, &mut outcome);
+    if let Err((side, message)) = played {
+        outcome.winner = Some(side.opposite());
+        outcome.forfeit = Some(message);
+    }
+
+    outcome
+}
+
+fn play_turns(
+    x: &dyn CubeBot,
+    o: &dyn CubeBot,
+    rules: &CubeRules,
+    cube: &mut Cube,
+    outcome: &mut CubeOutcome,
+) -> Result<(), (Side, String)> {
+    for (bot, side) in [(x, Side::X), (o, Side::O)] {
+        bot.start_game(cube.size, side)
+            .map_err(|e| (side, e.message))?;
+    }
+
+    let mut side = Side::X;
+    while !cube.is_full() {
+        let bot = match side {
+            Side::X => x,
+            Side::O => o,
+        };
+        let turn = bot
+            .calculate_best_turn(cube, side)
+            .map_err(|e| e.message)
+            .and_then(|coords| {
+                let turn = Turn3 { coords, side };
+                rules
+                    .place(cube, &turn)
+                    .map(|_| turn)
+                    .map_err(|e| e.message)
+            })
+            .map_err(|message| (side, message))?;
+        outcome.turns.push(turn);
+
+        outcome.winner = rules.winner_after(cube, &turn);
+        if outcome.winner.is_some() {
+            return Ok(());
+        }
+        side = side.opposite();
+    }
+
+    Ok(())
+}
+
+pub fn run_match(
+    first: &dyn CubeBot,
+    second: &dyn CubeBot,
+    games: usize,
+    size: usize,
+    mut on_game: impl FnMut(&GameResult),
+) -> MatchScore {
+    let mut score = MatchScore::default();
+
+    for game in 0..games {
+        // the bots take turns to play X
+        let (outcome, first_side) = match game % 2 {
+            0 => (play_game(first, second, size), Side::X),
+            _ => (play_game(second, first, size), Side::O),
+        };
+
+        let result = GameResult {
+            game,
+            first_side,
+            winner: outcome.winner,
+            turns: outcome.turns.len(),
+            forfeit: outcome.forfeit,
+        };
+        score.add_game(&result);
+        on_game(&result);
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bots::random_bot::RandomBot,
+        common::{cube::Coords3, error::BotError},
+    };
+
+    use super::*;
+
+    /// Plays the first empty cell.
+    struct FirstCellBot;
+
+    impl CubeBot for FirstCellBot {
+        fn calculate_best_turn(&self, cube: &Cube, _side: Side) -> Result<Coords3, BotError> {
+            cube.empty_cells()
+                .first()
+                .copied()
+                .ok_or_else(BotError::of_no_moves)
+        }
+    }
+
+    #[test]
+    fn first_cells_complete_layer_diagonal() {
+        let outcome = play_game(&FirstCellBot, &FirstCellBot, 3);
+
+        // X takes a1, c1, b2 and a3 on the bottom layer, the last three a diagonal
+        assert_eq!(outcome.winner, Some(Side::X));
+        let last = outcome.turns.last().unwrap();
+        assert_eq!(last.coords.to_algebraic(), "a3:1");
+        assert!(outcome.forfeit.is_none());
+    }
+
+    #[test]
+    fn plays_games_to_the_end() {
+        let score = run_match(&RandomBot::default(), &RandomBot::default(), 2, 4, |_| {});
+
+        assert_eq!(score.wins + score.draws + score.losses, 2);
+    }
+}
@@ -0,0 +1,358 @@
+use std::fmt::Display;
+
+use crate::common::{
+    bot::{Bot, Coords, Field, Side, Turn},
+    game_process::PlayInfo,
+    opening::{self, Opening, SideChoice, ADDED_STONES, PROPOSED_STONES},
+    rules::GameSettings,
+};
+
+pub struct GameOutcome {
+    pub winner: Option<Side>,
+    /// X unless opening rules let the sides be chosen.
+    pub first_side: Side,
+    pub turns: Vec<Turn>,
+    pub forfeit: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MatchScore {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// The side of the bot that broke the rules or failed, and why.
+type Fault = (Side, String);
+
+/// Plays a game where `first` places the opening stones under opening rules
+/// and plays X otherwise.
+pub fn play_game(first: &dyn Bot, second: &dyn Bot, settings: GameSettings) -> GameOutcome {
+    let mut field = settings.rules.new_field(settings.field_size);
+    let mut outcome = GameOutcome {
+        winner: None,
+        first_side: Side::X,
+        turns: vec![],
+        forfeit: None,
+    };
+
+    let played = start_game(first, second, Side::X, settings)
+        .and_then(|_| match settings.rules.opening {
+            Some(opening) => {
+                play_opening(first, second, opening, settings, &mut field, &mut outcome)
+            }
+            None => Ok(()),
+        })
+        .and_then(|_| play_turns(first, second, settings, &mut field, &mut outcome));
+    if let Err((side, message)) = played {
+        outcome.winner = Some(side.opposite());
+        outcome.forfeit = Some(message);
+    }
+
+    outcome
+}
+
+fn start_game(
+    first: &dyn Bot,
+    second: &dyn Bot,
+    first_side: Side,
+    settings: GameSettings,
+) -> Result<(), Fault> {
+    for (bot, side) in [(first, first_side), (second, first_side.opposite())] {
+        let play_info = PlayInfo {
+            field_size: settings.field_size,
+            win_condition: settings.win_condition,
+            rules: settings.rules,
+            side,
+            player_id: None,
+            game_id: None,
+        };
+        bot.start_game(&play_info).map_err(|e| (side, e.message))?;
+    }
+
+    Ok(())
+}
+
+/// Places the opening stones and lets the bots choose their sides. Until then
+/// `first` is at fault as X and `second` as O.
+fn play_opening(
+    first: &dyn Bot,
+    second: &dyn Bot,
+    opening: Opening,
+    settings: GameSettings,
+    field: &mut Field,
+    outcome: &mut GameOutcome,
+) -> Result<(), Fault> {
+    let stones = first
+        .propose_opening(field, PROPOSED_STONES)
+        .map_err(|e| (Side::X, e.message))?;
+    place_stones(field, &stones, PROPOSED_STONES, settings, outcome).map_err(|m| (Side::X, m))?;
+
+    let (mut chooser, mut side) = (second, Side::O);
+    let first_side = loop {
+        let can_add_stones = opening.can_add_stones(outcome.turns.len());
+        let choice = chooser
+            .choose_side(field, can_add_stones)
+            .map_err(|e| (side, e.message))?;
+
+        match choice {
+            SideChoice::Take(taken) if side == Side::X => break taken,
+            SideChoice::Take(taken) => break taken.opposite(),
+            SideChoice::AddStones(stones) if can_add_stones => {
+                place_stones(field, &stones, ADDED_STONES, settings, outcome)
+                    .map_err(|message| (side, message))?;
+                (chooser, side) = (first, Side::X);
+            }
+            SideChoice::AddStones(_) => {
+                return Err((side, "no more stones may be added".to_owned()));
+            }
+        }
+    };
+
+    outcome.first_side = first_side;
+    start_game(first, second, first_side, settings)
+}
+
+fn place_stones(
+    field: &mut Field,
+    stones: &[Coords],
+    count: usize,
+    settings: GameSettings,
+    outcome: &mut GameOutcome,
+) -> Result<(), String> {
+    let turns = opening::place_stones(field, stones, count, settings.rules, settings.win_condition)
+        .map_err(|e| e.message)?;
+    outcome.turns.extend(turns);
+
+    Ok(())
+}
+
+fn play_turns(
+    first: &dyn Bot,
+    second: &dyn Bot,
+    settings: GameSettings,
+    field: &mut Field,
+    outcome: &mut GameOutcome,
+) -> Result<(), Fault> {
+    let (x, o) = match outcome.first_side {
+        Side::X => (first, second),
+        Side::O => (second, first),
+    };
+
+    let mut side = opening::side_to_move(field);
+    while !settings.rules.is_exhausted(field) {
+        let bot = match side {
+            Side::X => x,
+            Side::O => o,
+        };
+        let turn = bot
+            .make_turn(field, side)
+            .map_err(|e| e.message)
+            .and_then(|turn| match turn.side == side {
+                true => settings
+                    .rules
+                    .place(field, &turn, settings.win_condition)
+                    .map_err(|e| e.message),
+                false => Err(format!("{side:?} made a turn for {:?}", turn.side)),
+            })
+            .map_err(|message| (side, message))?;
+        outcome.turns.push(turn);
+
+        outcome.winner = settings
+            .rules
+            .winner_after(field, &turn, settings.win_condition);
+        if outcome.winner.is_some() {
+            return Ok(());
+        }
+        side = side.opposite();
+    }
+
+    Ok(())
+}
+
+/// Plays `games` games between the bots, passing each one to `on_game` once it's over.
+pub fn run_match(
+    first: &dyn Bot,
+    second: &dyn Bot,
+    games: usize,
+    settings: GameSettings,
+    mut on_game: impl FnMut(&GameResult),
+) -> MatchScore {
+    let mut score = MatchScore::default();
+
+    for game in 0..games {
+        // the bots take turns to play X, or to open under opening rules
+        let (outcome, first_side) = match game % 2 {
+            0 => {
+                let outcome = play_game(first, second, settings);
+                let first_side = outcome.first_side;
+                (outcome, first_side)
+            }
+            _ => {
+                let outcome = play_game(second, first, settings);
+                let first_side = outcome.first_side.opposite();
+                (outcome, first_side)
+            }
+        };
+
+        let result = GameResult {
+            game,
+            first_side,
+            winner: outcome.winner,
+            turns: outcome.turns.len(),
+            forfeit: outcome.forfeit,
+        };
+        score.add_game(&result);
+        on_game(&result);
+    }
+
+    score
+}
+
+/// How a game of a match ended, whatever the board it was played on.
+pub struct GameResult {
+    /// 0-based.
+    pub game: usize,
+    /// The side of the bot that was first in the match.
+    pub first_side: Side,
+    pub winner: Option<Side>,
+    pub turns: usize,
+    pub forfeit: Option<String>,
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = match self.winner {
+            Some(winner) if winner == self.first_side => "first wins",
+            Some(_) => "second wins",
+            None => "draw",
+        };
+        write!(
+            f,
+            "game {}: first plays {:?}, {outcome} in {} turns",
+            self.game + 1,
+            self.first_side,
+            self.turns
+        )?;
+        if let Some(message) = &self.forfeit {
+            write!(f, " by forfeit ({message})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MatchScore {
+    pub fn add_game(&mut self, result: &GameResult) {
+        match result.winner {
+            Some(winner) if winner == result.first_side => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        bots::search_bot::SearchBot,
+        common::{
+            bot::{Cell, Size},
+            error::BotError,
+            rules::RuleSet,
+        },
+    };
+
+    use super::*;
+
+    /// Plays the first empty cell and answers the opening as told.
+    struct OpeningBot(SideChoice);
+
+    impl Bot for OpeningBot {
+        fn calculate_best_turn(&self, field: &Field, _side: Side) -> Result<Coords, BotError> {
+            (0..field.size.height as isize)
+                .flat_map(|y| (0..field.size.width as isize).map(move |x| Coords { x, y }))
+                .find(|&coords| matches!(field.get(coords), Some(Cell::Empty)))
+                .ok_or_else(BotError::of_no_moves)
+        }
+
+        fn choose_side(&self, _field: &Field, _can_add: bool) -> Result<SideChoice, BotError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn settings(rules: &str) -> GameSettings {
+        GameSettings {
+            field_size: Size {
+                width: 5,
+                height: 5,
+            },
+            win_condition: 4,
+            rules: RuleSet::parse(rules).unwrap(),
+        }
+    }
+
+    #[test]
+    fn plays_swap2_opening() {
+        let first = OpeningBot(SideChoice::Take(Side::O));
+        let added = vec![Coords { x: 0, y: 4 }, Coords { x: 4, y: 4 }];
+        let second = OpeningBot(SideChoice::AddStones(added));
+
+        let outcome = play_game(&first, &second, settings("swap2"));
+
+        assert!(outcome.forfeit.is_none());
+        assert_eq!(outcome.first_side, Side::O);
+        let opening: Vec<String> = outcome.turns[..6]
+            .iter()
+            .map(|turn| turn.coords.to_algebraic())
+            .collect();
+        assert_eq!(opening, ["a1", "b1", "c1", "a5", "e5", "d1"]);
+        assert_eq!(outcome.turns[5].side, Side::O);
+    }
+
+    #[test]
+    fn search_bot_wins_every_game() {
+        // depth bound, so the match plays the same way every time
+        let search = SearchBot::new(Duration::from_secs(10), 4, 1);
+        let naive = OpeningBot(SideChoice::Take(Side::X));
+
+        for _ in 0..2 {
+            let score = run_match(&search, &naive, 4, settings("standard"), |_| {});
+
+            assert_eq!((score.wins, score.draws, score.losses), (4, 0, 0));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a core per search thread, run with --ignored --release"]
+    fn lazy_smp_beats_single_thread_in_fixed_time() {
+        let settings = GameSettings {
+            field_size: Size {
+                width: 15,
+                height: 15,
+            },
+            win_condition: 5,
+            rules: RuleSet::STANDARD,
+        };
+        let time_limit = Duration::from_millis(200);
+        let parallel = SearchBot::new(time_limit, 64, 4);
+        let single = SearchBot::new(time_limit, 64, 1);
+
+        let score = run_match(&parallel, &single, 10, settings, |_| {});
+
+        assert!(score.wins > score.losses, "{score:?}");
+    }
+
+    #[test]
+    fn adding_stones_forfeits_in_swap() {
+        let first = OpeningBot(SideChoice::Take(Side::X));
+        let second = OpeningBot(SideChoice::AddStones(vec![]));
+
+        let outcome = play_game(&first, &second, settings("swap"));
+
+        assert_eq!(outcome.winner, Some(Side::X));
+        assert!(outcome.forfeit.is_some());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod cube_match;
+pub mod local_match;
@@ -0,0 +1,267 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::common::{
+    bot::{Cell, Side},
+    cube::{Coords3, Cube, CubeBot},
+    cube_rules::CubeRules,
+    error::BotError,
+};
+
+use super::search_bot::WIN_SCORE;
+
+static INFINITY: i32 = 10_000_000;
+static DECISIVE_SCORE: i32 = WIN_SCORE - 1_000;
+static MAX_BRANCHING: usize = 24;
+static NODES_BETWEEN_CLOCK_CHECKS: u64 = 1024;
+
+/// Alpha-beta search for Qubic, the cube counterpart of `SearchBot`.
+pub struct CubeSearchBot {
+    time_limit: Duration,
+    max_depth: usize,
+    rules: Mutex<Option<Arc<CubeRules>>>,
+}
+
+struct CubeSearch<'a> {
+    rules: &'a CubeRules,
+    cells: Vec<Option<Side>>,
+    deadline: Instant,
+    can_abort: bool,
+    nodes: u64,
+}
+
+impl CubeSearchBot {
+    pub fn new(time_limit: Duration, max_depth: usize) -> Self {
+        CubeSearchBot {
+            time_limit,
+            max_depth,
+            rules: Mutex::new(None),
+        }
+    }
+
+    fn rules(&self, size: usize) -> Arc<CubeRules> {
+        let mut rules = self.rules.lock().unwrap();
+        match rules.as_ref() {
+            Some(rules) if rules.size == size => rules.clone(),
+            _ => rules.insert(Arc::new(CubeRules::new(size))).clone(),
+        }
+    }
+}
+
+impl CubeBot for CubeSearchBot {
+    fn start_game(&self, size: usize, _side: Side) -> Result<(), BotError> {
+        self.rules(size);
+        Ok(())
+    }
+
+    fn calculate_best_turn(&self, cube: &Cube, side: Side) -> Result<Coords3, BotError> {
+        let rules = self.rules(cube.size);
+        let mut search = CubeSearch {
+            rules: &rules,
+            cells: cube
+                .cells()
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Value(side) => Some(*side),
+                    Cell::Empty => None,
+                })
+                .collect(),
+            deadline: Instant::now() + self.time_limit,
+            can_abort: false,
+            nodes: 0,
+        };
+
+        let best = search
+            .best_move(side, self.max_depth)
+            .ok_or_else(BotError::of_no_moves)?;
+        Ok(cube.coords(best))
+    }
+}
+
+impl CubeSearch<'_> {
+    fn best_move(&mut self, side: Side, max_depth: usize) -> Option<usize> {
+        let mut moves = self.ordered_moves(side);
+        let mut best = *moves.first()?;
+
+        for depth in 1..=max_depth.min(moves.len()) {
+            self.can_abort = depth > 1;
+            let mut alpha = -INFINITY;
+            let mut scored = vec![];
+            for &index in &moves {
+                self.cells[index] = Some(side);
+                let score = match self.wins(index, side) {
+                    true => Some(WIN_SCORE),
+                    false => self
+                        .negamax(side.opposite(), depth - 1, 1, -INFINITY, -alpha)
+                        .map(|score| -score),
+                };
+                self.cells[index] = None;
+
+                let Some(score) = score else {
+                    return Some(best);
+                };
+                alpha = alpha.max(score);
+                scored.push((index, score));
+            }
+
+            scored.sort_by_key(|&(_, score)| -score);
+            moves = scored.iter().map(|&(index, _)| index).collect();
+            best = moves[0];
+            if scored[0].1.abs() >= DECISIVE_SCORE {
+                break;
+            }
+        }
+
+        Some(best)
+    }
+
+    fn negamax(
+        &mut self,
+        side: Side,
+        depth: usize,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if self.can_abort
+            && self.nodes.is_multiple_of(NODES_BETWEEN_CLOCK_CHECKS)
+            && Instant::now() >= self.deadline
+        {
+            return None;
+        }
+
+        if depth == 0 {
+            return Some(self.evaluate(side));
+        }
+
+        let moves = self.ordered_moves(side);
+        if moves.is_empty() {
+            return Some(0);
+        }
+
+        let mut best_score = -INFINITY;
+        for index in moves.into_iter().take(MAX_BRANCHING) {
+            self.cells[index] = Some(side);
+            let score = match self.wins(index, side) {
+                true => Some(WIN_SCORE - ply),
+                false => self
+                    .negamax(side.opposite(), depth - 1, ply + 1, -beta, -alpha)
+                    .map(|score| -score),
+            };
+            self.cells[index] = None;
+
+            best_score = best_score.max(score?);
+            alpha = alpha.max(best_score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        Some(best_score)
+    }
+
+    /// Empty cells, the ones on the most promising lines first.
+    fn ordered_moves(&self, side: Side) -> Vec<usize> {
+        let mut moves: Vec<(usize, i32)> = (0..self.cells.len())
+            .filter(|&index| self.cells[index].is_none())
+            .map(|index| {
+                let priority = self
+                    .rules
+                    .lines_through(index)
+                    .iter()
+                    .map(|&line| match self.count(line, side) {
+                        (own, 0) => 2 * self.weight(own),
+                        (0, enemy) => self.weight(enemy),
+                        _ => 0,
+                    })
+                    .sum();
+                (index, priority)
+            })
+            .collect();
+        moves.sort_by_key(|&(_, priority)| -priority);
+
+        moves.into_iter().map(|(index, _)| index).collect()
+    }
+
+    fn evaluate(&self, side: Side) -> i32 {
+        (0..self.rules.lines().len())
+            .map(|line| match self.count(line, side) {
+                (own, 0) => self.weight(own),
+                (0, enemy) => -self.weight(enemy),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn wins(&self, index: usize, side: Side) -> bool {
+        self.rules
+            .lines_through(index)
+            .iter()
+            .any(|&line| self.count(line, side) == (self.rules.size, 0))
+    }
+
+    /// Stones of `side` and of the opponent on `line`.
+    fn count(&self, line: usize, side: Side) -> (usize, usize) {
+        self.rules.lines()[line]
+            .iter()
+            .filter_map(|&cell| self.cells[cell])
+            .fold((0, 0), |(own, enemy), stone| match stone == side {
+                true => (own + 1, enemy),
+                false => (own, enemy + 1),
+            })
+    }
+
+    fn weight(&self, stones: usize) -> i32 {
+        match self.rules.size - stones {
+            0 => WIN_SCORE,
+            1 => 1_000,
+            _ => 4_i32.pow(stones as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::cube::Turn3;
+
+    use super::*;
+
+    fn best_turn(size: usize, stones: &[(usize, usize, usize, Side)], side: Side) -> Coords3 {
+        let mut cube = Cube::new(size);
+        for &(x, y, z, stone) in stones {
+            let turn = Turn3 {
+                coords: Coords3 { x, y, z },
+                side: stone,
+            };
+            cube.add_turn(&turn).unwrap();
+        }
+        let bot = CubeSearchBot::new(Duration::from_secs(5), 4);
+        bot.start_game(size, side).unwrap();
+
+        bot.calculate_best_turn(&cube, side).unwrap()
+    }
+
+    #[test]
+    fn completes_space_diagonal() {
+        let stones = [
+            (0, 0, 0, Side::X),
+            (1, 1, 1, Side::X),
+            (2, 2, 2, Side::X),
+            (0, 1, 0, Side::O),
+            (0, 2, 0, Side::O),
+            (0, 3, 0, Side::O),
+        ];
+
+        assert_eq!(best_turn(4, &stones, Side::X), Coords3 { x: 3, y: 3, z: 3 });
+    }
+
+    #[test]
+    fn blocks_line_across_layers() {
+        let stones = [(1, 0, 0, Side::X), (1, 0, 1, Side::X), (0, 0, 0, Side::O)];
+
+        assert_eq!(best_turn(3, &stones, Side::O), Coords3 { x: 1, y: 0, z: 2 });
+    }
+}
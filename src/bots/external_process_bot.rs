@@ -0,0 +1,215 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    common::{
+        bot::{Bot, Coords, Field, Side},
+        error::BotError,
+        game_process::PlayInfo,
+        logging,
+    },
+    protocol::engine::{EngineReply, HostCommand, PROTOCOL_VERSION},
+};
+
+static STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+static TURN_GRACE_PERIOD: Duration = Duration::from_secs(1);
+static QUIT_GRACE_PERIOD: Duration = Duration::from_millis(500);
+static QUIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub struct ExternalProcessBot {
+    engine: Mutex<EngineProcess>,
+    time_limit: Duration,
+}
+
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<String>,
+    /// Replies to commands `receive` stopped waiting for, to skip when they come.
+    late_replies: usize,
+}
+
+impl ExternalProcessBot {
+    pub fn spawn(program: &str, args: &[String], time_limit: Duration) -> Result<Self, BotError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| BotError::of_engine(&format!("can't start {program}: {e}")))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = EngineProcess {
+            child,
+            stdin,
+            replies,
+            late_replies: 0,
+        };
+        engine.send(HostCommand::Protocol(PROTOCOL_VERSION))?;
+        match engine.receive(STARTUP_TIMEOUT)? {
+            EngineReply::Ok(name) => {
+                logging::info!("engine started: {}", name.unwrap_or_default())
+            }
+            reply => return Err(BotError::of_unexpected_reply(&reply)),
+        }
+
+        Ok(ExternalProcessBot {
+            engine: Mutex::new(engine),
+            time_limit,
+        })
+    }
+}
+
+impl Bot for ExternalProcessBot {
+    fn start_game(&self, play_info: &PlayInfo) -> Result<(), BotError> {
+        let mut engine = self.engine.lock().unwrap();
+        engine.send(HostCommand::Start {
+            size: play_info.field_size,
+            win_condition: play_info.win_condition,
+            rules: play_info.rules,
+        })?;
+
+        match engine.receive(STARTUP_TIMEOUT)? {
+            EngineReply::Ok(_) => Ok(()),
+            reply => Err(BotError::of_unexpected_reply(&reply)),
+        }
+    }
+
+    fn calculate_best_turn(&self, field: &Field, side: Side) -> Result<Coords, BotError> {
+        let stones = field.stones();
+
+        let mut engine = self.engine.lock().unwrap();
+        engine.send(HostCommand::Position { side, stones })?;
+        engine.send(HostCommand::Go(self.time_limit))?;
+
+        match engine.receive(self.time_limit + TURN_GRACE_PERIOD)? {
+            EngineReply::Move(coords) => Ok(coords),
+            reply => Err(BotError::of_unexpected_reply(&reply)),
+        }
+    }
+}
+
+impl EngineProcess {
+    fn send(&mut self, command: HostCommand) -> Result<(), BotError> {
+        writeln!(self.stdin, "{}", command.to_line())
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| BotError::of_engine(&e.to_string()))
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<EngineReply, BotError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.replies.recv_timeout(left) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.late_replies += 1;
+                    return Err(BotError::of_engine("engine didn't answer in time"));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(BotError::of_engine("engine closed its output"))
+                }
+            };
+
+            let reply = EngineReply::parse(&line);
+            if let Ok(EngineReply::Info(text)) = &reply {
+                logging::debug!("engine info: {text}");
+                continue;
+            }
+            if self.late_replies > 0 {
+                self.late_replies -= 1;
+                logging::debug!("engine answered late: {line}");
+                continue;
+            }
+
+            match reply? {
+                EngineReply::Error(text) => return Err(BotError::of_engine(&text)),
+                reply => return Ok(reply),
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send(HostCommand::Quit);
+        let deadline = Instant::now() + QUIT_GRACE_PERIOD;
+        while Instant::now() < deadline && matches!(self.child.try_wait(), Ok(None)) {
+            thread::sleep(QUIT_POLL_INTERVAL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process, time::Duration};
+
+    use crate::common::bot::{Bot, Coords, Field, Side, Size};
+
+    use super::ExternalProcessBot;
+
+    fn spawn(script: &str) -> ExternalProcessBot {
+        let args = ["-c".to_owned(), script.to_owned()];
+        ExternalProcessBot::spawn("sh", &args, Duration::from_millis(10)).unwrap()
+    }
+
+    #[test]
+    fn skips_late_reply_after_timeout() {
+        let bot = spawn(
+            "n=0; while read command rest; do case $command in
+                PROTOCOL) echo OK;;
+                GO) n=$((n + 1)); [ $n = 1 ] && sleep 1.5; echo MOVE $n $n;;
+                QUIT) exit;;
+            esac; done",
+        );
+        let field = Field::new(Size {
+            width: 3,
+            height: 3,
+        });
+
+        assert!(bot.calculate_best_turn(&field, Side::X).is_err());
+        assert_eq!(
+            bot.calculate_best_turn(&field, Side::X).unwrap(),
+            Coords { x: 2, y: 2 }
+        );
+    }
+
+    #[test]
+    fn lets_engine_quit_before_killing_it() {
+        let marker = env::temp_dir().join(format!("engine-quit-{}", process::id()));
+        let _ = fs::remove_file(&marker);
+        let bot = spawn(&format!(
+            "while read command rest; do case $command in
+                PROTOCOL) echo OK;;
+                QUIT) sleep 0.1; touch '{}'; exit;;
+            esac; done",
+            marker.display()
+        ));
+
+        drop(bot);
+
+        assert!(marker.exists());
+        fs::remove_file(&marker).unwrap();
+    }
+}
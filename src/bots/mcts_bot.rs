@@ -0,0 +1,321 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::ThreadRng, Rng};
+
+use crate::common::{
+    bot::{Analysis, Bot, Candidate, Coords, Field, Side},
+    error::BotError,
+    game_process::PlayInfo,
+    rules::RuleSet,
+    ultimate::UltimatePosition,
+};
+
+static EXPLORATION: f64 = 1.4;
+static ITERATIONS_BETWEEN_CLOCK_CHECKS: usize = 64;
+/// Candidate scores are the expected result in per mille, -1000 to 1000.
+static SCORE_SCALE: f64 = 1000.0;
+
+/// Monte Carlo tree search with random playouts, for ultimate tic-tac-toe.
+pub struct MctsBot {
+    time_limit: Duration,
+    max_iterations: usize,
+    rules: Mutex<RuleSet>,
+}
+
+struct Node {
+    coords: Option<Coords>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Coords>,
+    visits: u32,
+    /// Results for the side that moved to this node: 1 for a win, 0.5 for a draw.
+    score: f64,
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    root: UltimatePosition,
+    misere: bool,
+    rng: ThreadRng,
+}
+
+impl MctsBot {
+    pub fn new(time_limit: Duration, max_iterations: usize) -> Self {
+        MctsBot {
+            time_limit,
+            max_iterations,
+            rules: Mutex::new(RuleSet {
+                ultimate: true,
+                ..RuleSet::STANDARD
+            }),
+        }
+    }
+}
+
+impl Bot for MctsBot {
+    fn start_game(&self, play_info: &PlayInfo) -> Result<(), BotError> {
+        if !play_info.rules.ultimate {
+            return Err(BotError::of_unsupported_rules(&play_info.rules.to_string()));
+        }
+        *self.rules.lock().unwrap() = play_info.rules;
+        Ok(())
+    }
+
+    fn calculate_best_turn(&self, field: &Field, side: Side) -> Result<Coords, BotError> {
+        let analysis = self.analyze(field, side, 1)?.unwrap();
+        Ok(analysis.candidates[0].coords)
+    }
+
+    fn analyze(
+        &self,
+        field: &Field,
+        side: Side,
+        candidates: usize,
+    ) -> Result<Option<Analysis>, BotError> {
+        let rules = *self.rules.lock().unwrap();
+        let position = UltimatePosition::from_field(field, side)
+            .map_err(|e| BotError { message: e.message })?;
+        if let Some(coords) = winning_move(&position, rules) {
+            return Ok(Some(Analysis {
+                candidates: vec![Candidate {
+                    coords,
+                    score: SCORE_SCALE as i32,
+                    principal_variation: vec![coords],
+                }],
+                depth: 1,
+                nodes: 0,
+            }));
+        }
+        let mut tree = Tree::new(position, rules.misere);
+        if tree.nodes[0].untried.is_empty() {
+            return Err(BotError::of_no_moves());
+        }
+
+        let deadline = Instant::now() + self.time_limit;
+        let mut iterations = 0;
+        while iterations < self.max_iterations {
+            if iterations % ITERATIONS_BETWEEN_CLOCK_CHECKS == 0 && Instant::now() >= deadline {
+                break;
+            }
+            tree.iterate();
+            iterations += 1;
+        }
+
+        Ok(Some(tree.analysis(candidates.max(1), iterations as u64)))
+    }
+}
+
+/// A move that wins on the spot, which random playouts are slow to single out.
+fn winning_move(position: &UltimatePosition, rules: RuleSet) -> Option<Coords> {
+    if rules.misere {
+        return None;
+    }
+
+    position.legal_moves().into_iter().find(|&coords| {
+        let mut next = position.clone();
+        next.play(coords).is_ok() && next.winner() == Some(position.side())
+    })
+}
+
+impl Tree {
+    fn new(root: UltimatePosition, misere: bool) -> Self {
+        let root_node = Node {
+            coords: None,
+            parent: None,
+            children: vec![],
+            untried: root.legal_moves(),
+            visits: 0,
+            score: 0.0,
+        };
+
+        Tree {
+            nodes: vec![root_node],
+            root,
+            misere,
+            rng: rand::thread_rng(),
+        }
+    }
+
+    /// Selects a leaf, expands it by one move, plays the game out at random
+    /// and backs the result up.
+    fn iterate(&mut self) {
+        let mut position = self.root.clone();
+        let mut node = 0;
+
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select_child(node);
+            let _ = position.play(self.nodes[node].coords.unwrap());
+        }
+
+        if !self.nodes[node].untried.is_empty() {
+            let untried = &mut self.nodes[node].untried;
+            let coords = untried.swap_remove(self.rng.gen_range(0..untried.len()));
+            let _ = position.play(coords);
+            self.nodes.push(Node {
+                coords: Some(coords),
+                parent: Some(node),
+                children: vec![],
+                untried: position.legal_moves(),
+                visits: 0,
+                score: 0.0,
+            });
+            let child = self.nodes.len() - 1;
+            self.nodes[node].children.push(child);
+            node = child;
+        }
+
+        // the side that moved to the leaf
+        let leaf_side = position.side().opposite();
+        let winner = self.play_out(position);
+        let mut result = match winner {
+            Some(winner) if winner == leaf_side => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        let mut next = Some(node);
+        while let Some(index) = next {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.score += result;
+            result = 1.0 - result;
+            next = node.parent;
+        }
+    }
+
+    fn select_child(&self, node: usize) -> usize {
+        let log_visits = (self.nodes[node].visits as f64).ln();
+        let ucb = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits as f64;
+            child.score / visits + EXPLORATION * (log_visits / visits).sqrt()
+        };
+
+        let children = &self.nodes[node].children;
+        children
+            .iter()
+            .copied()
+            .max_by(|&first, &second| ucb(first).total_cmp(&ucb(second)))
+            .unwrap()
+    }
+
+    /// The winner of a random game from `position`.
+    fn play_out(&mut self, mut position: UltimatePosition) -> Option<Side> {
+        loop {
+            let moves = position.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let _ = position.play(moves[self.rng.gen_range(0..moves.len())]);
+        }
+
+        match self.misere {
+            true => position.winner().map(|side| side.opposite()),
+            false => position.winner(),
+        }
+    }
+
+    fn analysis(&self, candidates: usize, iterations: u64) -> Analysis {
+        let mut children = self.nodes[0].children.clone();
+        children.sort_by_key(|&child| std::cmp::Reverse(self.nodes[child].visits));
+
+        let candidates: Vec<Candidate> = children
+            .into_iter()
+            .take(candidates)
+            .map(|child| {
+                let node = &self.nodes[child];
+                let expected = node.score / node.visits.max(1) as f64;
+                Candidate {
+                    coords: node.coords.unwrap(),
+                    score: ((2.0 * expected - 1.0) * SCORE_SCALE).round() as i32,
+                    principal_variation: self.principal_variation(child),
+                }
+            })
+            .collect();
+        let depth = candidates
+            .iter()
+            .map(|candidate| candidate.principal_variation.len())
+            .max()
+            .unwrap_or_default();
+
+        Analysis {
+            candidates,
+            depth,
+            nodes: iterations,
+        }
+    }
+
+    /// The most visited line of moves from `node` on, its own move first.
+    fn principal_variation(&self, mut node: usize) -> Vec<Coords> {
+        let mut variation = vec![self.nodes[node].coords.unwrap()];
+        while let Some(&child) = self.nodes[node]
+            .children
+            .iter()
+            .max_by_key(|&&child| self.nodes[child].visits)
+        {
+            variation.push(self.nodes[child].coords.unwrap());
+            node = child;
+        }
+
+        variation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{bot::Size, ultimate::FIELD_SIZE};
+
+    use super::*;
+
+    fn play_info(rules: &str) -> PlayInfo {
+        PlayInfo {
+            field_size: FIELD_SIZE,
+            win_condition: 3,
+            rules: RuleSet::parse(rules).unwrap(),
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        }
+    }
+
+    #[test]
+    fn wins_third_board_in_a_row() {
+        // X has won the boards of a1 and e5, and can take the one of i9
+        let position = "9x9 3 X XXX6/OO7/O8/3XXX3/9/9/6XX1/9/9";
+        let field = Field::from_notation(position).unwrap().field;
+        let bot = MctsBot::new(Duration::from_secs(5), 3000);
+        bot.start_game(&play_info("ultimate")).unwrap();
+
+        let best_turn = bot.calculate_best_turn(&field, Side::X).unwrap();
+
+        assert_eq!(best_turn.to_algebraic(), "i7");
+    }
+
+    #[test]
+    fn blocks_third_board_in_a_row() {
+        // f6 sends O to the board of i9, where X threatens i7 to take its third
+        // board in a row, and h9 would let X play anywhere
+        let position = "9x9 3 O XXX6/OO7/O8/3X5/4X4/5X3/3XXXXX1/6OOX/6X1O f6";
+        let field = Field::from_notation(position).unwrap().field;
+        let bot = MctsBot::new(Duration::from_secs(5), 3000);
+        bot.start_game(&play_info("ultimate")).unwrap();
+
+        let best_turn = bot.calculate_best_turn(&field, Side::O).unwrap();
+
+        assert_eq!(best_turn.to_algebraic(), "i7");
+    }
+
+    #[test]
+    fn plays_only_ultimate() {
+        let bot = MctsBot::new(Duration::from_millis(100), 100);
+        let mut info = play_info("standard");
+        info.field_size = Size {
+            width: 3,
+            height: 3,
+        };
+
+        assert!(bot.start_game(&info).is_err());
+    }
+}
@@ -1 +1,81 @@
-pub mod random_bot;
\ No newline at end of file
+pub mod cube_search_bot;
+pub mod external_process_bot;
+pub mod mcts_bot;
+pub mod random_bot;
+pub mod search;
+pub mod search_bot;
+
+use std::time::Duration;
+
+use crate::common::{bot::Bot, cube::CubeBot, error::BotError};
+
+use self::{
+    cube_search_bot::CubeSearchBot, external_process_bot::ExternalProcessBot, mcts_bot::MctsBot,
+    random_bot::RandomBot, search_bot::SearchBot,
+};
+
+static EXTERNAL_TURN_TIME: Duration = Duration::from_secs(5);
+static SEARCH_TURN_TIME_MS: u64 = 1000;
+static SEARCH_MAX_DEPTH: usize = 64;
+static SEARCH_THREADS: usize = 1;
+static CUBE_SEARCH_MAX_DEPTH: usize = 6;
+static MCTS_MAX_ITERATIONS: usize = 1_000_000;
+
+pub fn from_spec(spec: &str) -> Result<Box<dyn Bot>, BotError> {
+    let mut parts = spec.split_whitespace();
+
+    match parts.next() {
+        Some("random") => Ok(Box::<RandomBot>::default()),
+        Some("external") => {
+            let program = parts.next().ok_or_else(|| BotError::of_unknown_bot(spec))?;
+            let args: Vec<String> = parts.map(str::to_owned).collect();
+            let bot = ExternalProcessBot::spawn(program, &args, EXTERNAL_TURN_TIME)?;
+            Ok(Box::new(bot))
+        }
+        Some("search") => {
+            let mut arg = |default| match parts.next() {
+                Some(value) => value.parse().map_err(|_| BotError::of_unknown_bot(spec)),
+                None => Ok(default),
+            };
+            let time_limit = Duration::from_millis(arg(SEARCH_TURN_TIME_MS)?);
+            let max_depth = arg(SEARCH_MAX_DEPTH as u64)? as usize;
+            let threads = arg(SEARCH_THREADS as u64)? as usize;
+            if max_depth == 0 {
+                return Err(BotError::of_bad_setting(spec, "depth"));
+            }
+            Ok(Box::new(SearchBot::new(time_limit, max_depth, threads)))
+        }
+        Some("mcts") => {
+            let mut arg = |default| match parts.next() {
+                Some(value) => value.parse().map_err(|_| BotError::of_unknown_bot(spec)),
+                None => Ok(default),
+            };
+            let time_limit = Duration::from_millis(arg(SEARCH_TURN_TIME_MS)?);
+            let max_iterations = arg(MCTS_MAX_ITERATIONS as u64)? as usize;
+            Ok(Box::new(MctsBot::new(time_limit, max_iterations)))
+        }
+        _ => Err(BotError::of_unknown_bot(spec)),
+    }
+}
+
+/// Bots for Qubic: "random" or "search [ms] [depth]".
+pub fn cube_from_spec(spec: &str) -> Result<Box<dyn CubeBot>, BotError> {
+    let mut parts = spec.split_whitespace();
+
+    match parts.next() {
+        Some("random") => Ok(Box::<RandomBot>::default()),
+        Some("search") => {
+            let mut arg = |default| match parts.next() {
+                Some(value) => value.parse().map_err(|_| BotError::of_unknown_bot(spec)),
+                None => Ok(default),
+            };
+            let time_limit = Duration::from_millis(arg(SEARCH_TURN_TIME_MS)?);
+            let max_depth = arg(CUBE_SEARCH_MAX_DEPTH as u64)? as usize;
+            if max_depth == 0 {
+                return Err(BotError::of_bad_setting(spec, "depth"));
+            }
+            Ok(Box::new(CubeSearchBot::new(time_limit, max_depth)))
+        }
+        _ => Err(BotError::of_unknown_bot(spec)),
+    }
+}
@@ -1,30 +1,46 @@
+use std::sync::Mutex;
+
 use rand::Rng;
 
-use crate::common::bot::{Bot, Cell, Coords};
+use crate::common::{
+    bot::{Bot, Coords, Field, Side},
+    cube::{Coords3, Cube, CubeBot},
+    error::BotError,
+    game_process::PlayInfo,
+    rules::RuleSet,
+};
 
+#[derive(Default)]
 pub struct RandomBot {
-
+    rules: Mutex<(RuleSet, usize)>,
 }
 
 impl Bot for RandomBot {
-    fn calculate_best_turn(
-        &self,
-        field: &crate::common::bot::Field,
-        _side: crate::common::bot::Side,
-    ) -> Result<crate::common::bot::Coords, crate::common::error::BotError> {
-        
-        let mut empty_coords = vec![];
-
-        for i in 0..field.size.height {
-            for j in 0..field.size.width {
-                if let Some(Cell::Empty) = field.geti(i, j) {
-                    empty_coords.push(Coords {x: i, y: j});
-                }
-            }
+    fn start_game(&self, play_info: &PlayInfo) -> Result<(), BotError> {
+        *self.rules.lock().unwrap() = (play_info.rules, play_info.win_condition);
+        Ok(())
+    }
+
+    fn calculate_best_turn(&self, field: &Field, side: Side) -> Result<Coords, BotError> {
+        let (rules, win_condition) = *self.rules.lock().unwrap();
+        let legal_moves = rules.legal_moves(field, side, win_condition);
+        if legal_moves.is_empty() {
+            return Err(BotError::of_no_moves());
+        }
+        let selected_index = rand::thread_rng().gen_range(0..legal_moves.len());
+
+        Ok(legal_moves[selected_index])
+    }
+}
+
+impl CubeBot for RandomBot {
+    fn calculate_best_turn(&self, cube: &Cube, _side: Side) -> Result<Coords3, BotError> {
+        let empty_cells = cube.empty_cells();
+        if empty_cells.is_empty() {
+            return Err(BotError::of_no_moves());
         }
-        let mut rng = rand::thread_rng();
-        let selected_index = rng.gen_range(0..empty_coords.len());
+        let selected_index = rand::thread_rng().gen_range(0..empty_cells.len());
 
-        Ok(empty_coords[selected_index])
+        Ok(empty_cells[selected_index])
     }
 }
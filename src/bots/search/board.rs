@@ -0,0 +1,320 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::common::{
+    bot::{Cell, Coords, Field, Side, Size},
+    renju::{self, Stones},
+    rules::RuleSet,
+    utils::{Direction, DIRECTIONS},
+};
+
+static ZOBRIST_SEED: u64 = 0x7ac7_ac70_e0b0_7001;
+static CANDIDATE_RADIUS: isize = 2;
+
+#[derive(Clone)]
+pub struct SearchBoard {
+    size: Size,
+    win_condition: usize,
+    rules: RuleSet,
+    cells: Vec<Option<Side>>,
+    zobrist: Vec<[u64; 2]>,
+    hash: u64,
+    stones: usize,
+    /// The board is a window of an infinite field, there's room away from the stones anyway.
+    is_window: bool,
+}
+
+impl SearchBoard {
+    pub fn from_field(field: &Field, win_condition: usize, rules: RuleSet) -> Self {
+        let size = field.size;
+        let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+        let mut board = SearchBoard {
+            size,
+            win_condition,
+            rules,
+            cells: vec![None; size.width * size.height],
+            zobrist: (0..size.width * size.height).map(|_| rng.gen()).collect(),
+            hash: 0,
+            stones: 0,
+            is_window: false,
+        };
+
+        for turn in field.stones() {
+            board.place(board.index(turn.coords), turn.side);
+        }
+
+        board
+    }
+
+    /// Takes the board for a window of an infinite field, where moves stay near the stones.
+    pub fn into_window(mut self) -> Self {
+        self.is_window = true;
+        self
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn index(&self, coords: Coords) -> usize {
+        coords.y as usize * self.size.width + coords.x as usize
+    }
+
+    pub fn coords(&self, index: usize) -> Coords {
+        Coords {
+            x: (index % self.size.width) as isize,
+            y: (index / self.size.width) as isize,
+        }
+    }
+
+    pub fn empty_cells(&self) -> usize {
+        self.cells.len() - self.stones
+    }
+
+    /// Whether `index` is a cell of the board with no stone on it.
+    pub fn is_empty_cell(&self, index: usize) -> bool {
+        matches!(self.cells.get(index), Some(None))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.stones == self.cells.len()
+    }
+
+    pub fn place(&mut self, index: usize, side: Side) {
+        self.cells[index] = Some(side);
+        self.hash ^= self.zobrist[index][side_index(side)];
+        self.stones += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if let Some(side) = self.cells[index].take() {
+            self.hash ^= self.zobrist[index][side_index(side)];
+            self.stones -= 1;
+        }
+    }
+
+    /// The side that won by the stone of `side` at `index`, if it decided the game.
+    pub fn winner_at(&self, index: usize, side: Side) -> Option<Side> {
+        let line = DIRECTIONS.iter().any(|dir| {
+            let length = self.line_length(index, side, (dir.x as isize, dir.y as isize));
+            match self.rules.is_exact_for(side) {
+                true => length == self.win_condition,
+                false => length >= self.win_condition,
+            }
+        });
+
+        match (line, self.rules.misere) {
+            (false, _) => None,
+            (true, false) => Some(side),
+            (true, true) => Some(side.opposite()),
+        }
+    }
+
+    /// Whether `side` may not play at the empty `index` by Renju rules.
+    pub fn is_forbidden(&self, index: usize, side: Side) -> bool {
+        if !self.rules.renju || side != Side::X {
+            return false;
+        }
+        let coords = self.coords(index);
+
+        renju::forbidden(self, (coords.x, coords.y), self.win_condition).is_some()
+    }
+
+    /// Empty cells worth searching. With gravity these are all the legal drops,
+    /// in misère every cell of a bounded field is, as moves away from the stones
+    /// are often the only safe ones.
+    pub fn candidates(&self) -> Vec<usize> {
+        if self.rules.gravity {
+            return (0..self.size.width)
+                .filter_map(|x| self.drop_target(x))
+                .collect();
+        }
+        if self.rules.misere && !self.is_window {
+            return (0..self.cells.len())
+                .filter(|&index| self.cells[index].is_none())
+                .collect();
+        }
+
+        if self.stones == 0 {
+            let center = Coords {
+                x: (self.size.width / 2) as isize,
+                y: (self.size.height / 2) as isize,
+            };
+            return vec![self.index(center)];
+        }
+
+        (0..self.cells.len())
+            .filter(|&index| self.cells[index].is_none() && self.has_neighbor(index))
+            .collect()
+    }
+
+    pub fn move_priority(&self, index: usize, side: Side) -> i32 {
+        // a run that would grow past the win condition counts for nothing
+        // when overlines don't win
+        let useful_run = |side: Side, dir: (isize, isize)| {
+            let run =
+                self.run_length(index, side, dir) + self.run_length(index, side, (-dir.0, -dir.1));
+            match self.rules.is_exact_for(side) && run >= self.win_condition {
+                true => 0,
+                false => run,
+            }
+        };
+        let priority: i32 = DIRECTIONS
+            .iter()
+            .map(|dir| {
+                let dir = (dir.x as isize, dir.y as isize);
+                let own = useful_run(side, dir);
+                let enemy = useful_run(side.opposite(), dir);
+                (own * own * 2 + enemy * enemy) as i32
+            })
+            .sum();
+
+        match self.rules.misere {
+            true => -priority,
+            false => priority,
+        }
+    }
+
+    pub fn evaluate(&self, side: Side) -> i32 {
+        let win = self.win_condition as isize;
+        let enemy_side = side.opposite();
+        let mut score = 0;
+
+        for index in 0..self.cells.len() {
+            for dir in DIRECTIONS {
+                // a window longer than the line itself would count stones twice
+                let too_short = self.period(dir).is_some_and(|period| period < win as usize);
+                let dir = (dir.x as isize, dir.y as isize);
+                if too_short || self.step(index, dir, win - 1).is_none() {
+                    continue;
+                }
+
+                let (mut own, mut enemy) = (0, 0);
+                for k in 0..win {
+                    match self.cells[self.step(index, dir, k).unwrap()] {
+                        Some(cell_side) if cell_side == side => own += 1,
+                        Some(_) => enemy += 1,
+                        None => {}
+                    }
+                }
+
+                match (own, enemy) {
+                    (own, 0) if own > 0 && !self.is_overline_window(index, dir, side) => {
+                        score += window_weight(own, win)
+                    }
+                    (0, enemy) if enemy > 0 && !self.is_overline_window(index, dir, enemy_side) => {
+                        score -= window_weight(enemy, win)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // in misère an open window is a line one may be forced to complete
+        match self.rules.misere {
+            true => -score,
+            false => score,
+        }
+    }
+
+    /// When overlines don't win for `side`, whether filling the window starting
+    /// at `index` would make one, so the window can never win.
+    fn is_overline_window(&self, index: usize, dir: (isize, isize), side: Side) -> bool {
+        let win = self.win_condition as isize;
+
+        self.rules.is_exact_for(side)
+            && [self.step(index, dir, -1), self.step(index, dir, win)]
+                .into_iter()
+                .flatten()
+                .any(|next| self.cells[next] == Some(side))
+    }
+
+    fn drop_target(&self, x: usize) -> Option<usize> {
+        (0..self.size.height)
+            .map(|y| y * self.size.width + x)
+            .find(|&index| self.cells[index].is_none())
+    }
+
+    fn line_length(&self, index: usize, side: Side, dir: (isize, isize)) -> usize {
+        let length =
+            1 + self.run_length(index, side, dir) + self.run_length(index, side, (-dir.0, -dir.1));
+
+        length.min(self.period(direction(dir)).unwrap_or(usize::MAX))
+    }
+
+    fn run_length(&self, index: usize, side: Side, dir: (isize, isize)) -> usize {
+        let limit = self.period(direction(dir)).unwrap_or(usize::MAX);
+        let mut length = 0;
+        while length + 1 < limit {
+            let Some(next) = self.step(index, dir, length as isize + 1) else {
+                break;
+            };
+            if self.cells[next] != Some(side) {
+                break;
+            }
+            length += 1;
+        }
+
+        length
+    }
+
+    fn has_neighbor(&self, index: usize) -> bool {
+        for dx in -CANDIDATE_RADIUS..=CANDIDATE_RADIUS {
+            for dy in -CANDIDATE_RADIUS..=CANDIDATE_RADIUS {
+                if let Some(next) = self.step(index, (dx, dy), 1) {
+                    if self.cells[next].is_some() {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    fn step(&self, index: usize, dir: (isize, isize), k: isize) -> Option<usize> {
+        let topology = self.rules.topology;
+        let next = topology.neighbor(self.size, self.coords(index), direction(dir), k)?;
+
+        Some(self.index(next))
+    }
+
+    fn period(&self, dir: Direction) -> Option<usize> {
+        self.rules.topology.period(self.size, dir)
+    }
+}
+
+impl Stones for SearchBoard {
+    fn stone(&self, x: isize, y: isize) -> Option<Cell> {
+        let (width, height) = (self.size.width as isize, self.size.height as isize);
+        if !(0..width).contains(&x) || !(0..height).contains(&y) {
+            return None;
+        }
+
+        Some(match self.cells[self.index(Coords { x, y })] {
+            Some(side) => Cell::Value(side),
+            None => Cell::Empty,
+        })
+    }
+}
+
+fn direction(dir: (isize, isize)) -> Direction {
+    Direction {
+        x: dir.0 as i32,
+        y: dir.1 as i32,
+    }
+}
+
+fn side_index(side: Side) -> usize {
+    match side {
+        Side::X => 0,
+        Side::O => 1,
+    }
+}
+
+fn window_weight(stones: isize, win_condition: isize) -> i32 {
+    match win_condition - stones {
+        0 => 100_000,
+        1 => 1_000,
+        _ => 4_i32.pow(stones as u32),
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static DEFAULT_SIZE_LOG2: u32 = 20;
+/// Moves are kept in the top bits, boards past this many cells store none.
+static MAX_MOVE_INDEX: usize = (1 << 22) - 2;
+
+#[derive(Clone, Copy)]
+pub struct TtEntry {
+    pub depth: usize,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+// Lock-free table shared by search threads. Every slot keeps `hash ^ data` next
+// to `data`, so an entry torn by a concurrent write fails the key check.
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        TranspositionTable {
+            slots: (0..1usize << DEFAULT_SIZE_LOG2)
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, hash: u64) -> Option<TtEntry> {
+        let slot = self.slot(hash);
+        let key = slot[0].load(Ordering::Relaxed);
+        let data = slot[1].load(Ordering::Relaxed);
+
+        match data != 0 && key ^ data == hash {
+            true => Some(unpack(data)),
+            false => None,
+        }
+    }
+
+    pub fn store(&self, hash: u64, entry: TtEntry) {
+        if let Some(old) = self.get(hash) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+
+        let slot = self.slot(hash);
+        let data = pack(entry);
+        slot[0].store(hash ^ data, Ordering::Relaxed);
+        slot[1].store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot[0].store(0, Ordering::Relaxed);
+            slot[1].store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, hash: u64) -> &[AtomicU64; 2] {
+        &self.slots[hash as usize & (self.slots.len() - 1)]
+    }
+}
+
+fn pack(entry: TtEntry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let best_move = entry
+        .best_move
+        .filter(|&index| index <= MAX_MOVE_INDEX)
+        .map_or(0, |index| index as u64 + 1);
+
+    (entry.score as u32 as u64)
+        | (entry.depth.min(0xff) as u64) << 32
+        | bound << 40
+        | best_move << 42
+}
+
+fn unpack(data: u64) -> TtEntry {
+    TtEntry {
+        score: data as u32 as i32,
+        depth: (data >> 32 & 0xff) as usize,
+        bound: match data >> 40 & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+        best_move: match data >> 42 {
+            0 => None,
+            index => Some(index as usize - 1),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, unpack, Bound, TranspositionTable, TtEntry, MAX_MOVE_INDEX};
+
+    fn entry(score: i32, best_move: Option<usize>) -> TtEntry {
+        TtEntry {
+            depth: 7,
+            score,
+            bound: Bound::Lower,
+            best_move,
+        }
+    }
+
+    #[test]
+    fn unpacks_packed_entry() {
+        for (score, best_move) in [(-1_000_000, Some(0)), (42, None), (3, Some(MAX_MOVE_INDEX))] {
+            let unpacked = unpack(pack(entry(score, best_move)));
+
+            assert_eq!(unpacked.score, score);
+            assert_eq!(unpacked.depth, 7);
+            assert!(unpacked.bound == Bound::Lower);
+            assert_eq!(unpacked.best_move, best_move);
+        }
+    }
+
+    #[test]
+    fn drops_move_too_large_to_pack() {
+        let unpacked = unpack(pack(entry(0, Some(70_000))));
+        assert_eq!(unpacked.best_move, Some(70_000));
+
+        let unpacked = unpack(pack(entry(0, Some(MAX_MOVE_INDEX + 1))));
+        assert_eq!(unpacked.best_move, None);
+    }
+
+    #[test]
+    fn rejects_entry_of_another_position() {
+        let table = TranspositionTable::new();
+        let hash = 0x1234_5678_9abc_def0;
+        table.store(hash, entry(5, Some(3)));
+
+        assert_eq!(table.get(hash).unwrap().best_move, Some(3));
+        // same slot, different key
+        assert!(table.get(hash ^ 1 << 63).is_none());
+
+        // a torn write leaves a key that does not match the data
+        let slot = table.slot(hash);
+        slot[1].store(
+            pack(entry(6, Some(4))),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        assert!(table.get(hash).is_none());
+
+        table.clear();
+        assert!(table.get(hash).is_none());
+    }
+}
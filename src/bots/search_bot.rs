@@ -0,0 +1,1077 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::common::{
+    bot::{Analysis, Bot, Candidate, Coords, Field, Side, Topology, Turn},
+    error::BotError,
+    game_process::PlayInfo,
+    logging,
+    rules::RuleSet,
+};
+
+use super::search::{
+    board::SearchBoard,
+    transposition_table::{Bound, TranspositionTable, TtEntry},
+};
+
+pub static WIN_SCORE: i32 = 1_000_000;
+static INFINITY: i32 = 10_000_000;
+static DECISIVE_SCORE: i32 = WIN_SCORE - 1_000;
+static DEFAULT_WIN_CONDITION: usize = 5;
+static MAX_BRANCHING: usize = 16;
+static O_TO_MOVE_KEY: u64 = 0x9e37_79b9_7f4a_7c15;
+static NODES_BETWEEN_CLOCK_CHECKS: u64 = 1024;
+static PONDER_CANDIDATES: usize = 3;
+static PONDER_TIME_LIMIT: Duration = Duration::from_secs(3600);
+/// Share of the time limit left to search after a pondering hit.
+static PONDER_HIT_TIME_DIVISOR: u32 = 4;
+
+pub struct SearchBot {
+    core: Arc<SearchCore>,
+    pondering: Mutex<Option<Pondering>>,
+    pondered: Mutex<Option<PonderedResult>>,
+}
+
+struct SearchCore {
+    time_limit: Duration,
+    max_depth: usize,
+    threads: usize,
+    win_condition: AtomicUsize,
+    rules: Mutex<RuleSet>,
+    tt: TranspositionTable,
+    /// Corner, width and height of the window last searched on an infinite
+    /// field. Keys and moves in `tt` are cell indices of that window.
+    window: Mutex<Option<(Coords, usize, usize)>>,
+}
+
+struct Pondering {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<PonderedResult>>,
+}
+
+struct PonderedResult {
+    hash: u64,
+    origin: Coords,
+    analysis: Analysis,
+}
+
+struct SearchContext<'a> {
+    deadline: Instant,
+    stop: &'a AtomicBool,
+    can_abort: bool,
+    nodes: u64,
+}
+
+#[derive(Clone, Copy)]
+struct RootMove {
+    index: usize,
+    score: i32,
+}
+
+impl SearchBot {
+    pub fn new(time_limit: Duration, max_depth: usize, threads: usize) -> Self {
+        SearchBot {
+            core: Arc::new(SearchCore {
+                time_limit,
+                max_depth: max_depth.max(1),
+                threads: threads.max(1),
+                win_condition: AtomicUsize::new(0),
+                rules: Mutex::new(RuleSet::STANDARD),
+                tt: TranspositionTable::new(),
+                window: Mutex::new(None),
+            }),
+            pondering: Mutex::new(None),
+            pondered: Mutex::new(None),
+        }
+    }
+
+    fn take_pondered(&self, field: &Field, side: Side) -> Option<Analysis> {
+        let pondered = self.pondered.lock().unwrap().take()?;
+        let (board, origin) = self.core.board(field);
+
+        match pondered.hash == position_hash(&board, side) && pondered.origin == origin {
+            true => Some(pondered.analysis),
+            false => None,
+        }
+    }
+}
+
+impl SearchCore {
+    fn ponder(&self, field: &Field, side: Side, stop: &AtomicBool) -> Option<PonderedResult> {
+        let enemy = side.opposite();
+        let prediction_deadline = Instant::now() + self.time_limit / 4;
+        let prediction = self
+            .search(field, enemy, 1, prediction_deadline, stop)
+            .ok()??;
+
+        let mut field = field.clone();
+        let predicted_turn = Turn {
+            coords: prediction.candidates[0].coords,
+            side: enemy,
+        };
+        field.add_turn(&predicted_turn).ok()?;
+        let win_condition = self.win_condition(&field);
+        let rules = *self.rules.lock().unwrap();
+        if rules
+            .winner_after(&field, &predicted_turn, win_condition)
+            .is_some()
+            || field.is_full()
+        {
+            return None;
+        }
+
+        let deadline = Instant::now() + PONDER_TIME_LIMIT;
+        let analysis = self
+            .search(&field, side, PONDER_CANDIDATES, deadline, stop)
+            .ok()??;
+        let (board, origin) = self.board(&field);
+
+        Some(PonderedResult {
+            hash: position_hash(&board, side),
+            origin,
+            analysis,
+        })
+    }
+
+    fn win_condition(&self, field: &Field) -> usize {
+        match self.win_condition.load(Ordering::Relaxed) {
+            0 if field.is_infinite() => DEFAULT_WIN_CONDITION,
+            0 => DEFAULT_WIN_CONDITION
+                .min(field.size.width)
+                .min(field.size.height),
+            win_condition => win_condition,
+        }
+    }
+
+    /// The board to search `field` on, and the cell of the field its bottom left
+    /// corner is at. Infinite fields are searched on a window around their stones.
+    fn board(&self, field: &Field) -> (SearchBoard, Coords) {
+        let win_condition = self.win_condition(field);
+        let mut rules = *self.rules.lock().unwrap();
+        if !field.is_infinite() {
+            let origin = Coords { x: 0, y: 0 };
+            return (SearchBoard::from_field(field, win_condition, rules), origin);
+        }
+
+        // a line through any stone fits in the window
+        let (window, origin) = field.window(win_condition);
+        let placement = Some((origin, window.size.width, window.size.height));
+        let mut last_placement = self.window.lock().unwrap();
+        if *last_placement != placement {
+            // the same index is another cell now
+            self.tt.clear();
+            *last_placement = placement;
+        }
+        rules.topology = Topology::Bounded;
+        (
+            SearchBoard::from_field(&window, win_condition, rules).into_window(),
+            origin,
+        )
+    }
+
+    fn search(
+        &self,
+        field: &Field,
+        side: Side,
+        candidates: usize,
+        deadline: Instant,
+        stop: &AtomicBool,
+    ) -> Result<Option<Analysis>, BotError> {
+        let (board, origin) = self.board(field);
+        let root_moves: Vec<RootMove> = order_moves(&board, board.candidates(), side, None)
+            .into_iter()
+            .filter(|&index| !board.is_forbidden(index, side))
+            .map(|index| RootMove { index, score: 0 })
+            .collect();
+        if root_moves.is_empty() {
+            return Err(BotError::of_no_moves());
+        }
+
+        let helpers_stop = AtomicBool::new(false);
+        let helper_nodes = AtomicU64::new(0);
+        let analysis = thread::scope(|scope| {
+            for helper in 1..self.threads {
+                let mut board = board.clone();
+                let mut root_moves = root_moves.clone();
+                let shift = helper % root_moves.len();
+                root_moves.rotate_left(shift);
+                let (helpers_stop, helper_nodes) = (&helpers_stop, &helper_nodes);
+
+                scope.spawn(move || {
+                    let mut context = SearchContext {
+                        deadline,
+                        stop: helpers_stop,
+                        can_abort: false,
+                        nodes: 0,
+                    };
+                    let first_depth = 1 + helper % 2;
+                    self.iterative_deepening(
+                        &mut board,
+                        side,
+                        1,
+                        root_moves,
+                        first_depth,
+                        &mut context,
+                    );
+                    helper_nodes.fetch_add(context.nodes, Ordering::Relaxed);
+                });
+            }
+
+            let mut context = SearchContext {
+                deadline,
+                stop,
+                can_abort: false,
+                nodes: 0,
+            };
+            let analysis = self.iterative_deepening(
+                &mut board.clone(),
+                side,
+                candidates,
+                root_moves,
+                1,
+                &mut context,
+            );
+            helpers_stop.store(true, Ordering::Relaxed);
+            analysis.map(|analysis| (analysis, context.nodes))
+        });
+
+        Ok(analysis.map(|(mut analysis, nodes)| {
+            analysis.nodes = nodes + helper_nodes.load(Ordering::Relaxed);
+            for candidate in &mut analysis.candidates {
+                candidate.coords = candidate.coords + origin;
+                for coords in &mut candidate.principal_variation {
+                    *coords = *coords + origin;
+                }
+            }
+            analysis
+        }))
+    }
+
+    fn iterative_deepening(
+        &self,
+        board: &mut SearchBoard,
+        side: Side,
+        candidates: usize,
+        mut root_moves: Vec<RootMove>,
+        first_depth: usize,
+        context: &mut SearchContext,
+    ) -> Option<Analysis> {
+        let mut analysis = None;
+        for depth in first_depth..=self.max_depth.min(board.empty_cells()) {
+            context.can_abort = depth > 1;
+            let Some(scored) =
+                self.search_root(board, side, depth, candidates, &root_moves, context)
+            else {
+                break;
+            };
+
+            root_moves = scored;
+            analysis = Some(Analysis {
+                candidates: root_moves
+                    .iter()
+                    .take(candidates)
+                    .map(|root_move| Candidate {
+                        coords: board.coords(root_move.index),
+                        score: root_move.score,
+                        principal_variation: self.principal_variation(
+                            board,
+                            root_move.index,
+                            side,
+                            depth,
+                        ),
+                    })
+                    .collect(),
+                depth,
+                nodes: context.nodes,
+            });
+
+            if root_moves[0].score.abs() >= DECISIVE_SCORE {
+                break;
+            }
+        }
+
+        analysis
+    }
+
+    fn search_root(
+        &self,
+        board: &mut SearchBoard,
+        side: Side,
+        depth: usize,
+        candidates: usize,
+        root_moves: &[RootMove],
+        context: &mut SearchContext,
+    ) -> Option<Vec<RootMove>> {
+        let mut scored = vec![];
+        let mut best_exact: Vec<i32> = vec![];
+
+        for root_move in root_moves {
+            let index = root_move.index;
+            board.place(index, side);
+
+            let score = if let Some(winner) = board.winner_at(index, side) {
+                decided_score(winner, side, 0)
+            } else if board.is_full() {
+                0
+            } else if best_exact.len() < candidates {
+                -self.negamax(
+                    board,
+                    side.opposite(),
+                    depth - 1,
+                    1,
+                    -INFINITY,
+                    INFINITY,
+                    context,
+                )?
+            } else {
+                let threshold = best_exact[candidates - 1];
+                let score = -self.negamax(
+                    board,
+                    side.opposite(),
+                    depth - 1,
+                    1,
+                    -threshold - 1,
+                    -threshold,
+                    context,
+                )?;
+                match score > threshold {
+                    true => -self.negamax(
+                        board,
+                        side.opposite(),
+                        depth - 1,
+                        1,
+                        -INFINITY,
+                        INFINITY,
+                        context,
+                    )?,
+                    false => score,
+                }
+            };
+
+            board.remove(index);
+            if best_exact.len() < candidates || score > best_exact[candidates - 1] {
+                best_exact.push(score);
+                best_exact.sort_by(|a, b| b.cmp(a));
+            }
+            scored.push(RootMove { index, score });
+        }
+
+        scored.sort_by_key(|root_move| -root_move.score);
+        Some(scored)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &self,
+        board: &mut SearchBoard,
+        side: Side,
+        depth: usize,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        context: &mut SearchContext,
+    ) -> Option<i32> {
+        context.nodes += 1;
+        if context.nodes.is_multiple_of(NODES_BETWEEN_CLOCK_CHECKS)
+            && (context.stop.load(Ordering::Relaxed)
+                || context.can_abort && Instant::now() >= context.deadline)
+        {
+            return None;
+        }
+
+        let hash = position_hash(board, side);
+        let entry = self.tt.get(hash);
+        let tt_move = self.tt_move(board, side);
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            let score = score_from_tt(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return Some(score),
+                Bound::Lower if score >= beta => return Some(score),
+                Bound::Upper if score <= alpha => return Some(score),
+                _ => {}
+            }
+        }
+
+        if depth == 0 {
+            return Some(board.evaluate(side));
+        }
+
+        let moves: Vec<usize> = order_moves(board, board.candidates(), side, tt_move)
+            .into_iter()
+            .filter(|&index| !board.is_forbidden(index, side))
+            .take(MAX_BRANCHING)
+            .collect();
+        if moves.is_empty() {
+            return Some(0);
+        }
+
+        let alpha_original = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        for index in moves {
+            board.place(index, side);
+            let score = if let Some(winner) = board.winner_at(index, side) {
+                Some(decided_score(winner, side, ply))
+            } else if board.is_full() {
+                Some(0)
+            } else {
+                self.negamax(
+                    board,
+                    side.opposite(),
+                    depth - 1,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    context,
+                )
+                .map(|score| -score)
+            };
+            board.remove(index);
+
+            let score = score?;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(index);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= alpha_original {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(
+            hash,
+            TtEntry {
+                depth,
+                score: score_to_tt(best_score, ply),
+                bound,
+                best_move,
+            },
+        );
+
+        Some(best_score)
+    }
+
+    /// The best move stored for the position, if it's still a move on `board`.
+    fn tt_move(&self, board: &SearchBoard, side: Side) -> Option<usize> {
+        self.tt
+            .get(position_hash(board, side))
+            .and_then(|entry| entry.best_move)
+            .filter(|&index| board.is_empty_cell(index))
+    }
+
+    fn principal_variation(
+        &self,
+        board: &mut SearchBoard,
+        first: usize,
+        side: Side,
+        depth: usize,
+    ) -> Vec<Coords> {
+        let mut played = vec![(first, side)];
+        board.place(first, side);
+
+        let mut side = side.opposite();
+        while played.len() < depth
+            && board
+                .winner_at(played.last().unwrap().0, played.last().unwrap().1)
+                .is_none()
+        {
+            let Some(index) = self.tt_move(board, side) else {
+                break;
+            };
+            board.place(index, side);
+            played.push((index, side));
+            side = side.opposite();
+        }
+
+        let variation = played
+            .iter()
+            .map(|&(index, _)| board.coords(index))
+            .collect();
+        for &(index, _) in played.iter().rev() {
+            board.remove(index);
+        }
+
+        variation
+    }
+}
+
+impl Bot for SearchBot {
+    fn start_game(&self, play_info: &PlayInfo) -> Result<(), BotError> {
+        // the board doesn't know which sub-board a move is sent to
+        if play_info.rules.ultimate {
+            return Err(BotError::of_unsupported_rules("ultimate"));
+        }
+        self.stop_pondering();
+        self.pondered.lock().unwrap().take();
+        // keys are cell indices, the same for boards of other sizes
+        self.core.tt.clear();
+        self.core.window.lock().unwrap().take();
+        self.core
+            .win_condition
+            .store(play_info.win_condition, Ordering::Relaxed);
+        *self.core.rules.lock().unwrap() = play_info.rules;
+        Ok(())
+    }
+
+    fn calculate_best_turn(&self, field: &Field, side: Side) -> Result<Coords, BotError> {
+        let analysis = self.analyze(field, side, 1)?.unwrap();
+        Ok(analysis.candidates[0].coords)
+    }
+
+    fn analyze(
+        &self,
+        field: &Field,
+        side: Side,
+        candidates: usize,
+    ) -> Result<Option<Analysis>, BotError> {
+        self.stop_pondering();
+        let candidates = candidates.max(1);
+        let pondered = self
+            .take_pondered(field, side)
+            .filter(|pondered| pondered.candidates.len() >= candidates);
+
+        // the pondered search left its work in the table, a short search
+        // catches up with it and may go deeper
+        let time_limit = match pondered {
+            Some(_) => self.core.time_limit / PONDER_HIT_TIME_DIVISOR,
+            None => self.core.time_limit,
+        };
+        let deadline = Instant::now() + time_limit;
+        let stop = AtomicBool::new(false);
+        let analysis = self
+            .core
+            .search(field, side, candidates, deadline, &stop)?
+            .ok_or_else(BotError::of_no_analysis)?;
+
+        match pondered {
+            Some(pondered) if pondered.depth > analysis.depth => {
+                logging::debug!("pondering hit, reusing depth {} search", pondered.depth);
+                Ok(Some(pondered))
+            }
+            _ => Ok(Some(analysis)),
+        }
+    }
+
+    fn start_pondering(&self, field: &Field, side: Side) -> Result<(), BotError> {
+        self.stop_pondering();
+        self.pondered.lock().unwrap().take();
+
+        let core = self.core.clone();
+        let field = field.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || core.ponder(&field, side, &stop))
+        };
+
+        *self.pondering.lock().unwrap() = Some(Pondering { stop, handle });
+        Ok(())
+    }
+
+    fn stop_pondering(&self) {
+        let Some(pondering) = self.pondering.lock().unwrap().take() else {
+            return;
+        };
+
+        pondering.stop.store(true, Ordering::Relaxed);
+        if let Ok(Some(result)) = pondering.handle.join() {
+            *self.pondered.lock().unwrap() = Some(result);
+        }
+    }
+}
+
+impl Drop for SearchBot {
+    fn drop(&mut self) {
+        self.stop_pondering();
+    }
+}
+
+fn order_moves(
+    board: &SearchBoard,
+    mut moves: Vec<usize>,
+    side: Side,
+    first: Option<usize>,
+) -> Vec<usize> {
+    moves.sort_by_cached_key(|&index| match Some(index) == first {
+        true => i32::MIN,
+        false => -board.move_priority(index, side),
+    });
+
+    moves
+}
+
+/// Score of a game `winner` decided at `ply`, from the view of `side`.
+fn decided_score(winner: Side, side: Side, ply: i32) -> i32 {
+    match winner == side {
+        true => WIN_SCORE - ply - 1,
+        false => -(WIN_SCORE - ply - 1),
+    }
+}
+
+fn position_hash(board: &SearchBoard, side: Side) -> u64 {
+    match side {
+        Side::X => board.hash(),
+        Side::O => board.hash() ^ O_TO_MOVE_KEY,
+    }
+}
+
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score >= DECISIVE_SCORE {
+        score + ply
+    } else if score <= -DECISIVE_SCORE {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score >= DECISIVE_SCORE {
+        score - ply
+    } else if score <= -DECISIVE_SCORE {
+        score + ply
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::common::{
+        bot::{Bot, Cell, Coords, Field, Side, Size, Turn, MAX_WINDOW},
+        game_process::PlayInfo,
+        rules::RuleSet,
+    };
+
+    use super::{
+        super::search::{
+            board::SearchBoard,
+            transposition_table::{Bound, TtEntry},
+        },
+        position_hash, SearchBot, WIN_SCORE,
+    };
+
+    fn best_turn(notation: &str) -> Coords {
+        best_turn_with_rules(notation, RuleSet::STANDARD)
+    }
+
+    fn best_turn_with_rules(notation: &str, rules: RuleSet) -> Coords {
+        let position = Field::from_notation(notation).unwrap();
+        let bot = SearchBot::new(Duration::from_secs(5), 4, 1);
+        bot.start_game(&PlayInfo {
+            field_size: position.field.size,
+            win_condition: position.win_condition,
+            rules,
+            side: position.side,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        bot.calculate_best_turn(&position.field, position.side)
+            .unwrap()
+    }
+
+    #[test]
+    fn takes_immediate_win() {
+        assert_eq!(
+            best_turn("3x3 3 X XX1/OO1/3"),
+            Coords::from_algebraic("c1").unwrap()
+        );
+    }
+
+    #[test]
+    fn blocks_immediate_loss() {
+        assert_eq!(
+            best_turn("3x3 3 X X2/OO1/2X"),
+            Coords::from_algebraic("c2").unwrap()
+        );
+    }
+
+    #[test]
+    fn parallel_search_plays_legal_moves() {
+        let size = Size {
+            width: 9,
+            height: 9,
+        };
+        let bot = SearchBot::new(Duration::from_millis(50), 64, 4);
+        bot.start_game(&PlayInfo {
+            field_size: size,
+            win_condition: 5,
+            rules: RuleSet::STANDARD,
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        let mut field = Field::new(size);
+        let mut side = Side::X;
+        for _ in 0..8 {
+            let coords = bot.calculate_best_turn(&field, side).unwrap();
+            assert!(matches!(field.get(coords), Some(Cell::Empty)));
+            field.add_turn(&Turn { coords, side }).unwrap();
+            side = side.opposite();
+        }
+    }
+
+    #[test]
+    fn avoids_completing_line_in_misere() {
+        let misere = RuleSet::parse("misere").unwrap();
+
+        assert_ne!(
+            best_turn_with_rules("3x3 3 X XX1/OO1/3", misere),
+            Coords::from_algebraic("c1").unwrap()
+        );
+    }
+
+    #[test]
+    fn drops_stones_with_gravity() {
+        let gravity = RuleSet::parse("gravity").unwrap();
+
+        assert_eq!(best_turn_with_rules("5x5 4 X 5/5/5/5/5", gravity).y, 0);
+        assert_eq!(
+            best_turn_with_rules("4x4 3 O XO2/X3/4/4", gravity),
+            Coords::from_algebraic("a3").unwrap()
+        );
+    }
+
+    #[test]
+    fn completes_line_across_edge_on_torus() {
+        let position = "7x7 4 X XX4X/7/OOO4/7/7/7/7";
+        let torus = RuleSet::parse("torus").unwrap();
+
+        let turn = best_turn_with_rules(position, torus).to_algebraic();
+        assert!(["c1", "f1"].contains(&turn.as_str()));
+        assert_eq!(best_turn(position), Coords::from_algebraic("d3").unwrap());
+    }
+
+    #[test]
+    fn completes_line_at_negative_coords_on_infinite_field() {
+        let mut field = Field::infinite();
+        for x in -4..0 {
+            for (y, side) in [(-9, Side::X), (3, Side::O)] {
+                let coords = Coords { x, y };
+                field.add_turn(&Turn { coords, side }).unwrap();
+            }
+        }
+        let bot = SearchBot::new(Duration::from_secs(5), 4, 1);
+        bot.start_game(&PlayInfo {
+            field_size: field.size,
+            win_condition: 5,
+            rules: RuleSet::parse("infinite").unwrap(),
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        let turn = bot.calculate_best_turn(&field, Side::X).unwrap();
+
+        assert!([Coords { x: -5, y: -9 }, Coords { x: 0, y: -9 }].contains(&turn));
+    }
+
+    #[test]
+    fn ignores_distant_stones_on_infinite_field() {
+        let mut field = Field::infinite();
+        for x in 0..4 {
+            let coords = Coords { x, y: 0 };
+            field
+                .add_turn(&Turn {
+                    coords,
+                    side: Side::X,
+                })
+                .unwrap();
+        }
+        for (x, y) in [(1_000_000_000, 0), (isize::MAX, isize::MIN), (-3, 2)] {
+            let coords = Coords { x, y };
+            field
+                .add_turn(&Turn {
+                    coords,
+                    side: Side::O,
+                })
+                .unwrap();
+        }
+        let bot = SearchBot::new(Duration::from_secs(5), 4, 1);
+        bot.start_game(&PlayInfo {
+            field_size: field.size,
+            win_condition: 5,
+            rules: RuleSet::parse("infinite").unwrap(),
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        let turn = bot.calculate_best_turn(&field, Side::X).unwrap();
+
+        assert!([Coords { x: -1, y: 0 }, Coords { x: 4, y: 0 }].contains(&turn));
+        let (_, width, height) = bot.core.window.lock().unwrap().unwrap();
+        assert!(width <= MAX_WINDOW && height <= MAX_WINDOW);
+    }
+
+    #[test]
+    fn forgets_table_when_window_moves() {
+        let bot = SearchBot::new(Duration::from_secs(5), 4, 1);
+        bot.start_game(&PlayInfo {
+            field_size: Size {
+                width: 0,
+                height: 0,
+            },
+            win_condition: 5,
+            rules: RuleSet::parse("infinite").unwrap(),
+            side: Side::O,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+        let mut field = Field::infinite();
+        let mut play = |x, y, side| {
+            let coords = Coords { x, y };
+            field.add_turn(&Turn { coords, side }).unwrap();
+            field.clone()
+        };
+        let entry = TtEntry {
+            depth: 1,
+            score: 0,
+            bound: Bound::Exact,
+            best_move: Some(0),
+        };
+
+        let field = play(0, 0, Side::X);
+        let (board, _) = bot.core.board(&field);
+        let hash = position_hash(&board, Side::O);
+        bot.core.tt.store(hash, entry);
+        bot.core.board(&field);
+        assert!(bot.core.tt.get(hash).is_some());
+
+        // a stone to the left shifts the window, a1 of the old one is another cell
+        let field = play(-1, 0, Side::O);
+        bot.core.board(&field);
+        assert!(bot.core.tt.get(hash).is_none());
+    }
+
+    #[test]
+    fn stays_near_stones_in_misere_on_infinite_field() {
+        let rules = RuleSet::parse("infinite,misere").unwrap();
+        let mut field = rules.new_field(Size {
+            width: 0,
+            height: 0,
+        });
+        let bot = SearchBot::new(Duration::from_millis(50), 3, 1);
+        bot.start_game(&PlayInfo {
+            field_size: field.size,
+            win_condition: 5,
+            rules,
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        let mut side = Side::X;
+        for _ in 0..6 {
+            let turn = bot.make_turn(&field, side).unwrap();
+            assert!(rules.legal_moves(&field, side, 5).contains(&turn.coords));
+            rules.place(&mut field, &turn, 5).unwrap();
+            side = side.opposite();
+        }
+    }
+
+    #[test]
+    fn overline_does_not_win_with_exact_rule() {
+        let position = "9x9 5 X 9/9/9/9/XXXX1X3/9/OOOO5/9/9";
+        let exact = RuleSet::parse("exact").unwrap();
+
+        assert_eq!(best_turn(position), Coords::from_algebraic("e5").unwrap());
+        assert_eq!(
+            best_turn_with_rules(position, exact),
+            Coords::from_algebraic("e7").unwrap()
+        );
+    }
+
+    #[test]
+    fn overline_move_gets_no_priority_with_exact_rule() {
+        let position = Field::from_notation("9x9 5 X 9/9/9/9/XXXX1X3/9/OOOO5/9/9").unwrap();
+        let priorities = |rules| {
+            let board = SearchBoard::from_field(&position.field, 5, rules);
+            let [overline, block] =
+                ["e5", "e7"].map(|name| board.index(Coords::from_algebraic(name).unwrap()));
+            (
+                board.move_priority(overline, Side::X),
+                board.move_priority(block, Side::X),
+            )
+        };
+
+        let (overline, block) = priorities(RuleSet::STANDARD);
+        assert!(overline > block);
+        let (overline, block) = priorities(RuleSet::parse("exact").unwrap());
+        assert!(overline < block);
+    }
+
+    #[test]
+    fn never_makes_forbidden_move_with_renju() {
+        let position = "9x9 5 X O8/9/4X4/4X4/2XX5/9/9/9/8O";
+        let renju = RuleSet::parse("renju").unwrap();
+
+        assert_ne!(
+            best_turn_with_rules(position, renju),
+            Coords::from_algebraic("e5").unwrap()
+        );
+    }
+
+    #[test]
+    fn exploits_forbidden_point_with_renju() {
+        // f8 wins for X unless overlines are forbidden, then X can't block e8-f8
+        let position = "9x9 5 O 9/9/5O3/5X3/5X3/5X3/5X3/XOOO5/5X3";
+        let renju = RuleSet::parse("renju").unwrap();
+
+        assert_eq!(best_turn(position), Coords::from_algebraic("f8").unwrap());
+        assert_eq!(
+            best_turn_with_rules(position, renju),
+            Coords::from_algebraic("e8").unwrap()
+        );
+    }
+
+    #[test]
+    fn finds_open_four_in_gomoku() {
+        let turn = best_turn("9x9 5 X 9/9/9/9/2XXX4/9/2OOO4/9/9");
+        assert!([Coords { x: 1, y: 4 }, Coords { x: 5, y: 4 }].contains(&turn));
+    }
+
+    #[test]
+    fn analysis_ranks_candidates() {
+        let position = Field::from_notation("3x3 3 O 3/1X1/3").unwrap();
+        let bot = SearchBot::new(Duration::from_secs(5), 9, 1);
+
+        let analysis = bot
+            .analyze(&position.field, position.side, 8)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(analysis.candidates.len(), 8);
+        assert!(analysis.nodes > 0);
+        assert_eq!(analysis.candidates[0].score, 0);
+        assert!(analysis.candidates[7].score <= -WIN_SCORE / 2);
+        for window in analysis.candidates.windows(2) {
+            assert!(window[0].score >= window[1].score);
+        }
+        assert_eq!(
+            analysis.candidates[0].principal_variation[0],
+            analysis.candidates[0].coords
+        );
+    }
+
+    #[test]
+    fn pondering_hit_saves_time() {
+        // X has to block at f5, which pondering O predicts
+        let position = Field::from_notation("9x9 5 X X7X/9/9/9/XOOOO4/9/9/9/X8").unwrap();
+        let time_limit = Duration::from_secs(2);
+        let bot = SearchBot::new(time_limit, 64, 1);
+        bot.start_game(&PlayInfo {
+            field_size: position.field.size,
+            win_condition: 5,
+            rules: RuleSet::STANDARD,
+            side: Side::O,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+
+        bot.start_pondering(&position.field, Side::O).unwrap();
+        // the prediction takes up to a quarter of the time limit
+        thread::sleep(time_limit / 2);
+        let mut field = position.field.clone();
+        let block = Turn {
+            coords: Coords::from_algebraic("f5").unwrap(),
+            side: Side::X,
+        };
+        field.add_turn(&block).unwrap();
+        let started = Instant::now();
+        let analysis = bot.analyze(&field, Side::O, 1).unwrap().unwrap();
+
+        assert!(
+            started.elapsed() < time_limit / 2,
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(!analysis.candidates.is_empty());
+        assert!(bot.pondering.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn analysis_stops_pondering() {
+        let position = Field::from_notation("9x9 5 O 9/9/9/9/4X4/9/9/9/9").unwrap();
+        let bot = SearchBot::new(Duration::from_millis(50), 64, 1);
+
+        bot.start_pondering(&position.field, Side::X).unwrap();
+        let analysis = bot.analyze(&position.field, Side::O, 1).unwrap().unwrap();
+
+        assert!(!analysis.candidates.is_empty());
+        assert!(bot.pondering.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn searches_at_least_one_ply() {
+        let position = Field::from_notation("3x3 3 X XX1/OO1/3").unwrap();
+        let bot = SearchBot::new(Duration::from_secs(5), 0, 1);
+
+        let best_turn = bot.calculate_best_turn(&position.field, Side::X).unwrap();
+
+        assert_eq!(best_turn, Coords::from_algebraic("c1").unwrap());
+        assert!(crate::bots::from_spec("search 1000 0").is_err());
+    }
+
+    #[test]
+    fn ignores_stale_tt_moves() {
+        let position = Field::from_notation("3x3 3 O 3/1X1/3").unwrap();
+        let bot = SearchBot::new(Duration::from_secs(5), 4, 1);
+        let board = SearchBoard::from_field(&position.field, 3, RuleSet::STANDARD);
+        let hash = position_hash(&board, Side::O);
+        let entry = |best_move| TtEntry {
+            depth: 1,
+            score: 0,
+            bound: Bound::Upper,
+            best_move: Some(best_move),
+        };
+
+        // past the end of the board, and the cell of X
+        bot.core.tt.store(hash, entry(200));
+        assert_eq!(bot.core.tt_move(&board, Side::O), None);
+        bot.core.tt.store(hash, entry(4));
+        assert_eq!(bot.core.tt_move(&board, Side::O), None);
+        bot.core.tt.store(hash, entry(0));
+        assert_eq!(bot.core.tt_move(&board, Side::O), Some(0));
+
+        bot.start_game(&PlayInfo {
+            field_size: position.field.size,
+            win_condition: 3,
+            rules: RuleSet::STANDARD,
+            side: Side::O,
+            player_id: None,
+            game_id: None,
+        })
+        .unwrap();
+        assert!(bot.core.tt.get(hash).is_none());
+    }
+}
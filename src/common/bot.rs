@@ -1,11 +1,20 @@
-use std::ops::Add;
+use std::{collections::HashMap, fmt::Display, ops::Add};
 
 use super::{
     error::{BotError, CoreError},
+    game_process::PlayInfo,
+    opening::{self, SideChoice},
     utils::Direction,
 };
 
+/// Cells a window of an infinite field spans at most each way.
+pub static MAX_WINDOW: usize = 64;
+
 pub trait Bot {
+    fn start_game(&self, _play_info: &PlayInfo) -> Result<(), BotError> {
+        Ok(())
+    }
+
     fn make_turn(&self, field: &Field, side: Side) -> Result<Turn, BotError> {
         let best_turn = Turn {
             coords: self.calculate_best_turn(field, side)?,
@@ -16,11 +25,81 @@ pub trait Bot {
     }
 
     fn calculate_best_turn(&self, field: &Field, side: Side) -> Result<Coords, BotError>;
+
+    fn analyze(
+        &self,
+        _field: &Field,
+        _side: Side,
+        _candidates: usize,
+    ) -> Result<Option<Analysis>, BotError> {
+        Ok(None)
+    }
+
+    /// Stones to open the game with under opening rules, placed alternately
+    /// starting with the side to move. By default these are the bot's own moves.
+    fn propose_opening(&self, field: &Field, stones: usize) -> Result<Vec<Coords>, BotError> {
+        let mut field = field.clone();
+        let mut proposed = vec![];
+        for _ in 0..stones {
+            let side = opening::side_to_move(&field);
+            let coords = self.calculate_best_turn(&field, side)?;
+            field
+                .add_turn(&Turn { coords, side })
+                .map_err(|e| BotError { message: e.message })?;
+            proposed.push(coords);
+        }
+
+        Ok(proposed)
+    }
+
+    /// Picks a side after the opponent's opening stones, or with `can_add_stones`
+    /// (Swap2) hands the choice back. By default the side the analysis prefers.
+    fn choose_side(&self, field: &Field, _can_add_stones: bool) -> Result<SideChoice, BotError> {
+        let side = opening::side_to_move(field);
+        let score = self
+            .analyze(field, side, 1)?
+            .and_then(|analysis| analysis.candidates.first().map(|candidate| candidate.score));
+
+        match score {
+            Some(score) if score < 0 => Ok(SideChoice::Take(side.opposite())),
+            _ => Ok(SideChoice::Take(side)),
+        }
+    }
+
+    fn start_pondering(&self, _field: &Field, _side: Side) -> Result<(), BotError> {
+        Ok(())
+    }
+
+    fn stop_pondering(&self) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub candidates: Vec<Candidate>,
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub coords: Coords,
+    pub score: i32,
+    pub principal_variation: Vec<Coords>,
 }
 
+#[derive(Clone)]
 pub struct Field {
-    cells: Vec<Cell>,
+    cells: Cells,
+    /// Zero on infinite fields, which have no edges.
     pub size: Size,
+    last_move: Option<Coords>,
+}
+
+#[derive(Clone)]
+enum Cells {
+    Dense(Vec<Cell>),
+    /// Infinite fields keep only their stones.
+    Sparse(HashMap<Coords, Side>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -29,16 +108,18 @@ pub struct Size {
     pub height: usize,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Turn {
     pub coords: Coords,
     pub side: Side,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Cells of bounded fields are at non-negative coordinates, infinite fields
+/// go on in every direction.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Coords {
-    pub x: usize,
-    pub y: usize,
+    pub x: isize,
+    pub y: isize,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -48,6 +129,17 @@ pub enum Cell {
     Empty,
 }
 
+/// How lines continue past the edges of the field.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Topology {
+    #[default]
+    Bounded,
+    /// Lines wrap around: the left edge continues the right one, the bottom the top.
+    Torus,
+    /// The field has no edges, see `Field::infinite`.
+    Infinite,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Side {
     X,
@@ -57,42 +149,210 @@ pub enum Side {
 impl Field {
     pub fn new(size: Size) -> Self {
         Field {
-            cells: vec![Cell::Empty; size.width * size.height],
+            cells: Cells::Dense(vec![Cell::Empty; size.width * size.height]),
             size,
+            last_move: None,
+        }
+    }
+
+    /// An empty field without edges, holding only the stones placed on it.
+    pub fn infinite() -> Self {
+        Field {
+            cells: Cells::Sparse(HashMap::new()),
+            size: Size {
+                width: 0,
+                height: 0,
+            },
+            last_move: None,
         }
     }
 
+    pub fn is_infinite(&self) -> bool {
+        matches!(self.cells, Cells::Sparse(_))
+    }
+
     pub fn get(&self, coords: Coords) -> Option<Cell> {
-        self.geti(coords.x, coords.y)
+        match &self.cells {
+            Cells::Dense(cells) => self.index(coords).map(|index| cells[index]),
+            Cells::Sparse(stones) => Some(
+                stones
+                    .get(&coords)
+                    .map_or(Cell::Empty, |&side| Cell::Value(side)),
+            ),
+        }
     }
 
     pub fn geti(&self, x: usize, y: usize) -> Option<Cell> {
-        match x < self.size.width && y < self.size.height {
-            true => Some(self.cells[x * self.size.width + y]),
-            false => None,
-        }
+        self.get(Coords {
+            x: x as isize,
+            y: y as isize,
+        })
     }
 
     pub fn add_turn(&mut self, turn: &Turn) -> Result<(), CoreError> {
         match self.get(turn.coords) {
             Some(Cell::Empty) => {
-                self.cells[turn.coords.x * self.size.width + turn.coords.y] =
-                    Cell::Value(turn.side);
+                let index = self.index(turn.coords);
+                match &mut self.cells {
+                    Cells::Dense(cells) => cells[index.unwrap()] = Cell::Value(turn.side),
+                    Cells::Sparse(stones) => {
+                        stones.insert(turn.coords, turn.side);
+                    }
+                }
+                self.last_move = Some(turn.coords);
                 Ok(())
             }
             Some(Cell::Value(current)) => Err(CoreError::of_filled_cell(turn.coords, current)),
             None => Err(CoreError::of_wrong_coords(turn.coords, self.size)),
         }
     }
+
+    pub fn is_full(&self) -> bool {
+        match &self.cells {
+            Cells::Dense(cells) => cells.iter().all(|cell| matches!(cell, Cell::Value(_))),
+            Cells::Sparse(_) => false,
+        }
+    }
+
+    /// Every stone on the field, row by row from the bottom.
+    pub fn stones(&self) -> Vec<Turn> {
+        let mut stones: Vec<Turn> = match &self.cells {
+            Cells::Dense(cells) => (0..cells.len())
+                .filter_map(|index| match cells[index] {
+                    Cell::Value(side) => Some(Turn {
+                        coords: Coords {
+                            x: (index % self.size.width) as isize,
+                            y: (index / self.size.width) as isize,
+                        },
+                        side,
+                    }),
+                    Cell::Empty => None,
+                })
+                .collect(),
+            Cells::Sparse(stones) => stones
+                .iter()
+                .map(|(&coords, &side)| Turn { coords, side })
+                .collect(),
+        };
+        stones.sort_by_key(|turn| (turn.coords.y, turn.coords.x));
+
+        stones
+    }
+
+    /// A bounded copy of the stones with `margin` empty cells around them, and
+    /// the cell of this field its bottom left corner is at. It grows from the stone
+    /// with the most others around it to the nearest ones until it spans `MAX_WINDOW`
+    /// cells, so stones far from the play don't make it huge.
+    pub fn window(&self, margin: usize) -> (Field, Coords) {
+        let stones = self.stones();
+        let distance = |a: Coords, b: Coords| a.x.abs_diff(b.x).max(a.y.abs_diff(b.y));
+        let center = stones
+            .iter()
+            .map(|turn| turn.coords)
+            .max_by_key(|&coords| {
+                let around = |turn: &&Turn| distance(turn.coords, coords) < MAX_WINDOW / 2;
+                stones.iter().filter(around).count()
+            })
+            .unwrap_or(Coords { x: 0, y: 0 });
+        let mut nearest: Vec<Coords> = stones.iter().map(|turn| turn.coords).collect();
+        nearest.sort_by_key(|&coords| distance(coords, center));
+
+        let limit = MAX_WINDOW.max(2 * margin + 1);
+        let span = |min: isize, max: isize| max.abs_diff(min).saturating_add(2 * margin + 1);
+        let (mut min, mut max) = (center, center);
+        for coords in nearest {
+            let (x_min, x_max) = (min.x.min(coords.x), max.x.max(coords.x));
+            let (y_min, y_max) = (min.y.min(coords.y), max.y.max(coords.y));
+            if span(x_min, x_max) <= limit && span(y_min, y_max) <= limit {
+                min = Coords { x: x_min, y: y_min };
+                max = Coords { x: x_max, y: y_max };
+            }
+        }
+
+        let margin = margin as isize;
+        let origin = Coords {
+            x: min.x.saturating_sub(margin),
+            y: min.y.saturating_sub(margin),
+        };
+        let mut window = Field::new(Size {
+            width: max.x.saturating_add(margin).abs_diff(origin.x) + 1,
+            height: max.y.saturating_add(margin).abs_diff(origin.y) + 1,
+        });
+        let shift = |coords: Coords| {
+            Some(Coords {
+                x: coords.x.checked_sub(origin.x)?,
+                y: coords.y.checked_sub(origin.y)?,
+            })
+        };
+        for turn in stones {
+            if let Some(coords) = shift(turn.coords) {
+                // stones outside the window are rejected
+                let _ = window.add_turn(&Turn { coords, ..turn });
+            }
+        }
+        window.last_move = self.last_move.and_then(shift);
+
+        (window, origin)
+    }
+
+    fn index(&self, coords: Coords) -> Option<usize> {
+        let (width, height) = (self.size.width as isize, self.size.height as isize);
+        match (0..width).contains(&coords.x) && (0..height).contains(&coords.y) {
+            true => Some((coords.y * width + coords.x) as usize),
+            false => None,
+        }
+    }
+
+    /// Places `stones`, given in any order, and takes `last_move` for the
+    /// latest turn when one of them is there.
+    pub fn with_stones(
+        mut self,
+        stones: &[Turn],
+        last_move: Option<Coords>,
+    ) -> Result<Self, CoreError> {
+        for turn in stones {
+            self.add_turn(turn)?;
+        }
+        self.last_move =
+            last_move.filter(|&coords| matches!(self.get(coords), Some(Cell::Value(_))));
+
+        Ok(self)
+    }
+
+    /// The cell of the latest turn, which ultimate rules restrict the next one by.
+    pub fn last_move(&self) -> Option<Coords> {
+        self.last_move
+    }
 }
 
-impl Add<Direction> for Coords {
-    type Output = Coords;
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "depth {}, {} nodes", self.depth, self.nodes)?;
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            let variation: Vec<String> = candidate
+                .principal_variation
+                .iter()
+                .map(|coords| coords.to_algebraic())
+                .collect();
+            write!(
+                f,
+                "\n{:>3}. {:<4} {:>+8}  {}",
+                i + 1,
+                candidate.coords.to_algebraic(),
+                candidate.score,
+                variation.join(" ")
+            )?;
+        }
 
-    fn add(self, rhs: Direction) -> Self::Output {
-        Coords {
-            x: add(self.x, rhs.x),
-            y: add(self.y, rhs.y),
+        Ok(())
+    }
+}
+
+impl Side {
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::X => Side::O,
+            Side::O => Side::X,
         }
     }
 }
@@ -108,10 +368,43 @@ impl Add<Coords> for Coords {
     }
 }
 
-fn add(u: usize, i: i32) -> usize {
-    if i.is_negative() {
-        u - i.wrapping_abs() as u32 as usize
-    } else {
-        u + i as usize
+impl Topology {
+    /// The cell `k` steps from `coords` along `dir`, `None` past the edge of a bounded field.
+    pub fn neighbor(&self, size: Size, coords: Coords, dir: Direction, k: isize) -> Option<Coords> {
+        let (width, height) = (size.width as isize, size.height as isize);
+        let x = coords.x.checked_add(dir.x as isize * k)?;
+        let y = coords.y.checked_add(dir.y as isize * k)?;
+
+        match self {
+            Topology::Bounded if (0..width).contains(&x) && (0..height).contains(&y) => {
+                Some(Coords { x, y })
+            }
+            Topology::Bounded => None,
+            Topology::Torus => Some(Coords {
+                x: x.rem_euclid(width),
+                y: y.rem_euclid(height),
+            }),
+            Topology::Infinite => Some(Coords { x, y }),
+        }
+    }
+
+    /// How many cells a line along `dir` passes before it comes back to where
+    /// it started, `None` when it never does.
+    pub fn period(&self, size: Size, dir: Direction) -> Option<usize> {
+        match self {
+            Topology::Bounded | Topology::Infinite => None,
+            Topology::Torus => {
+                let width = if dir.x != 0 { size.width } else { 1 };
+                let height = if dir.y != 0 { size.height } else { 1 };
+                Some(width / gcd(width, height) * height)
+            }
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
     }
 }
@@ -0,0 +1,107 @@
+//! Qubic: tic-tac-toe on a cube of `size` cells a side, 3x3x3 or 4x4x4,
+//! where `size` in a row along any of the 13 directions wins.
+//!
+//! Cells are written as the algebraic name of the cell in their layer followed
+//! by the 1-based layer, e.g. `b3:2` is `x = 1, y = 2, z = 1`.
+
+use super::{
+    bot::{Cell, Coords, Side},
+    error::{BotError, CoreError},
+};
+
+pub static CUBE_SIZES: [usize; 2] = [3, 4];
+
+pub trait CubeBot {
+    fn start_game(&self, _size: usize, _side: Side) -> Result<(), BotError> {
+        Ok(())
+    }
+
+    fn calculate_best_turn(&self, cube: &Cube, side: Side) -> Result<Coords3, BotError>;
+}
+
+#[derive(Clone)]
+pub struct Cube {
+    cells: Vec<Cell>,
+    pub size: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Coords3 {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Turn3 {
+    pub coords: Coords3,
+    pub side: Side,
+}
+
+impl Cube {
+    pub fn new(size: usize) -> Self {
+        Cube {
+            cells: vec![Cell::Empty; size * size * size],
+            size,
+        }
+    }
+
+    pub fn get(&self, coords: Coords3) -> Option<Cell> {
+        let size = self.size;
+        match coords.x < size && coords.y < size && coords.z < size {
+            true => Some(self.cells[self.index(coords)]),
+            false => None,
+        }
+    }
+
+    pub fn add_turn(&mut self, turn: &Turn3) -> Result<(), CoreError> {
+        match self.get(turn.coords) {
+            Some(Cell::Empty) => {
+                let index = self.index(turn.coords);
+                self.cells[index] = Cell::Value(turn.side);
+                Ok(())
+            }
+            Some(Cell::Value(current)) => Err(CoreError::of_filled_cell_3d(turn.coords, current)),
+            None => Err(CoreError::of_outside_cube(turn.coords, self.size)),
+        }
+    }
+
+    pub fn empty_cells(&self) -> Vec<Coords3> {
+        (0..self.cells.len())
+            .filter(|&index| matches!(self.cells[index], Cell::Empty))
+            .map(|index| self.coords(index))
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().all(|cell| matches!(cell, Cell::Value(_)))
+    }
+
+    /// Cells in order of their index, x first, then y, then z.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn index(&self, coords: Coords3) -> usize {
+        (coords.z * self.size + coords.y) * self.size + coords.x
+    }
+
+    pub fn coords(&self, index: usize) -> Coords3 {
+        Coords3 {
+            x: index % self.size,
+            y: index / self.size % self.size,
+            z: index / (self.size * self.size),
+        }
+    }
+}
+
+impl Coords3 {
+    pub fn to_algebraic(self) -> String {
+        let layer_cell = Coords {
+            x: self.x as isize,
+            y: self.y as isize,
+        };
+
+        format!("{}:{}", layer_cell.to_algebraic(), self.z + 1)
+    }
+}
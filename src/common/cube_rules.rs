@@ -0,0 +1,130 @@
+//! Rules of Qubic, see `common::cube`.
+
+use super::{
+    bot::{Cell, Side},
+    cube::{Coords3, Cube, Turn3},
+    error::CoreError,
+    utils::DIRECTIONS_3D,
+};
+
+/// Every winning line of a cube, as cell indices, so checks don't walk the
+/// directions each time.
+pub struct CubeRules {
+    pub size: usize,
+    lines: Vec<Vec<usize>>,
+    lines_through: Vec<Vec<usize>>,
+}
+
+impl CubeRules {
+    pub fn new(size: usize) -> Self {
+        let cube = Cube::new(size);
+        let inside = |coord: isize| (0..size as isize).contains(&coord);
+        let mut lines = vec![];
+
+        for start in 0..size * size * size {
+            let Coords3 { x, y, z } = cube.coords(start);
+            for dir in DIRECTIONS_3D {
+                let cells: Option<Vec<usize>> = (0..size as isize)
+                    .map(|k| {
+                        let x = x as isize + dir.x as isize * k;
+                        let y = y as isize + dir.y as isize * k;
+                        let z = z as isize + dir.z as isize * k;
+                        (inside(x) && inside(y) && inside(z)).then(|| {
+                            cube.index(Coords3 {
+                                x: x as usize,
+                                y: y as usize,
+                                z: z as usize,
+                            })
+                        })
+                    })
+                    .collect();
+                // a line spans the whole cube, so only its first cell starts it
+                lines.extend(cells);
+            }
+        }
+
+        let mut lines_through = vec![vec![]; size * size * size];
+        for (line, cells) in lines.iter().enumerate() {
+            for &cell in cells {
+                lines_through[cell].push(line);
+            }
+        }
+
+        CubeRules {
+            size,
+            lines,
+            lines_through,
+        }
+    }
+
+    pub fn lines(&self) -> &[Vec<usize>] {
+        &self.lines
+    }
+
+    /// Lines through the cell at `index`, as indices into `lines`.
+    pub fn lines_through(&self, index: usize) -> &[usize] {
+        &self.lines_through[index]
+    }
+
+    pub fn place(&self, cube: &mut Cube, turn: &Turn3) -> Result<(), CoreError> {
+        if cube.size != self.size {
+            return Err(CoreError::of_outside_cube(turn.coords, self.size));
+        }
+
+        cube.add_turn(turn)
+    }
+
+    /// The side that won by `turn`, if it decided the game.
+    pub fn winner_after(&self, cube: &Cube, turn: &Turn3) -> Option<Side> {
+        self.winning_line(cube, turn).is_some().then_some(turn.side)
+    }
+
+    pub fn winning_line(&self, cube: &Cube, turn: &Turn3) -> Option<Vec<Coords3>> {
+        self.lines_through(cube.index(turn.coords))
+            .iter()
+            .map(|&line| &self.lines[line])
+            .find(|cells| {
+                cells
+                    .iter()
+                    .all(|&cell| matches!(cube.cells()[cell], Cell::Value(s) if s == turn.side))
+            })
+            .map(|cells| cells.iter().map(|&cell| cube.coords(cell)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_lines_of_qubic_cubes() {
+        assert_eq!(CubeRules::new(3).lines().len(), 49);
+        assert_eq!(CubeRules::new(4).lines().len(), 76);
+        // the center of a 3x3x3 cube is on a line in every direction
+        assert_eq!(CubeRules::new(3).lines_through(13).len(), 13);
+    }
+
+    #[test]
+    fn space_diagonal_wins() {
+        let rules = CubeRules::new(4);
+        let mut cube = Cube::new(4);
+        let turn = |k: usize| Turn3 {
+            coords: Coords3 {
+                x: k,
+                y: 3 - k,
+                z: k,
+            },
+            side: Side::O,
+        };
+
+        for k in 0..3 {
+            rules.place(&mut cube, &turn(k)).unwrap();
+            assert_eq!(rules.winner_after(&cube, &turn(k)), None);
+        }
+        rules.place(&mut cube, &turn(3)).unwrap();
+
+        assert_eq!(rules.winner_after(&cube, &turn(3)), Some(Side::O));
+        assert_eq!(rules.winning_line(&cube, &turn(3)).unwrap().len(), 4);
+        assert!(rules.place(&mut cube, &turn(3)).is_err());
+    }
+}
@@ -1,4 +1,12 @@
-use super::bot::{Coords, Size, Side};
+use std::fmt::Display;
+
+use crate::protocol::engine::EngineReply;
+
+use super::{
+    bot::{Coords, Side, Size},
+    cube::Coords3,
+    renju::Forbidden,
+};
 
 #[derive(Debug)]
 pub struct BotError {
@@ -15,11 +23,18 @@ pub struct CoreError {
     pub message: String,
 }
 
+#[derive(Debug)]
+pub struct ServerError {
+    pub status: u16,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub enum GameProcessError {
     Bot(BotError),
     Hook(HookError),
     Core(CoreError),
+    Interrupted,
 }
 
 impl CoreError {
@@ -34,4 +49,229 @@ impl CoreError {
             message: format!("Cell ${coords:?} is filled. Current value: ${current:?}")
         }
     }
-}
\ No newline at end of file
+
+    pub fn of_outside_cube(coords: Coords3, size: usize) -> Self {
+        CoreError {
+            message: format!(
+                "Cell {} is outside of the cube of size {size}",
+                coords.to_algebraic()
+            ),
+        }
+    }
+
+    pub fn of_filled_cell_3d(coords: Coords3, current: Side) -> Self {
+        CoreError {
+            message: format!(
+                "Cell {} is filled. Current value: {current:?}",
+                coords.to_algebraic()
+            ),
+        }
+    }
+
+    pub fn of_notation(notation: &str) -> Self {
+        CoreError {
+            message: format!("Wrong notation: {notation:?}"),
+        }
+    }
+
+    pub fn of_panic() -> Self {
+        CoreError {
+            message: "Game process panicked".to_owned(),
+        }
+    }
+
+    pub fn of_full_column(x: isize) -> Self {
+        CoreError {
+            message: format!("Column {x} is full"),
+        }
+    }
+
+    pub fn of_forbidden(coords: Coords, forbidden: Forbidden) -> Self {
+        CoreError {
+            message: format!(
+                "Move {} is forbidden for X: {forbidden}",
+                coords.to_algebraic()
+            ),
+        }
+    }
+
+    pub fn of_field_cells(size: Size) -> Self {
+        CoreError {
+            message: format!(
+                "Field cells don't match its size {}x{}",
+                size.width, size.height
+            ),
+        }
+    }
+
+    pub fn of_ultimate_field(size: Size) -> Self {
+        CoreError {
+            message: format!(
+                "Ultimate tic-tac-toe is played on a 9x9 field, not {}x{}",
+                size.width, size.height
+            ),
+        }
+    }
+
+    pub fn of_wrong_board(coords: Coords, board: Coords) -> Self {
+        CoreError {
+            message: format!(
+                "Move {} is outside board {} the opponent sent to",
+                coords.to_algebraic(),
+                board.to_algebraic()
+            ),
+        }
+    }
+
+    pub fn of_decided_board(coords: Coords) -> Self {
+        CoreError {
+            message: format!(
+                "Move {} is on a board already decided",
+                coords.to_algebraic()
+            ),
+        }
+    }
+
+    pub fn of_opening(message: &str) -> Self {
+        CoreError {
+            message: format!("Wrong opening: {message}"),
+        }
+    }
+
+    pub fn of_incompatible_rules(first: &str, second: &str) -> Self {
+        CoreError {
+            message: format!("Rules {first} and {second} can't be combined"),
+        }
+    }
+
+    pub fn of_unknown_rules(rules: &str) -> Self {
+        CoreError {
+            message: format!("Unknown rules: {rules:?}"),
+        }
+    }
+}
+
+impl BotError {
+    pub fn of_engine(message: &str) -> Self {
+        BotError {
+            message: format!("Engine failure: {message}"),
+        }
+    }
+
+    pub fn of_protocol(line: &str) -> Self {
+        BotError {
+            message: format!("Can't parse engine reply: {line:?}"),
+        }
+    }
+
+    pub fn of_unexpected_reply(reply: &EngineReply) -> Self {
+        BotError {
+            message: format!("Unexpected engine reply: {reply:?}"),
+        }
+    }
+
+    pub fn of_no_moves() -> Self {
+        BotError {
+            message: "There are no moves left".to_owned(),
+        }
+    }
+
+    pub fn of_unsupported_rules(rules: &str) -> Self {
+        BotError {
+            message: format!("The bot doesn't play {rules} rules"),
+        }
+    }
+
+    pub fn of_unknown_bot(spec: &str) -> Self {
+        BotError {
+            message: format!("Unknown bot: {spec:?}"),
+        }
+    }
+
+    pub fn of_bad_setting(spec: &str, setting: &str) -> Self {
+        BotError {
+            message: format!("Bad {setting} for bot {spec:?}"),
+        }
+    }
+
+    pub fn of_no_analysis() -> Self {
+        BotError {
+            message: "The search ended without a result".to_owned(),
+        }
+    }
+}
+
+impl HookError {
+    pub fn of_interrupted() -> Self {
+        HookError {
+            message: "Interrupted by shutdown".to_owned(),
+        }
+    }
+
+    pub fn of_opening_unsupported() -> Self {
+        HookError {
+            message: "These hooks don't support opening rules".to_owned(),
+        }
+    }
+
+    pub fn of_reattach_unsupported() -> Self {
+        HookError {
+            message: "These hooks can't reattach to a game".to_owned(),
+        }
+    }
+}
+
+impl ServerError {
+    pub fn of_io(message: &str) -> Self {
+        ServerError {
+            status: 500,
+            message: message.to_owned(),
+        }
+    }
+
+    pub fn of_bad_request(message: &str) -> Self {
+        ServerError {
+            status: 400,
+            message: message.to_owned(),
+        }
+    }
+
+    pub fn of_not_found(path: &str) -> Self {
+        ServerError {
+            status: 404,
+            message: format!("No such endpoint: {path}"),
+        }
+    }
+
+    pub fn of_unknown_player(id: &str) -> Self {
+        ServerError {
+            status: 404,
+            message: format!("Unknown player: {id}"),
+        }
+    }
+
+    pub fn of_no_game(id: &str) -> Self {
+        ServerError {
+            status: 404,
+            message: format!("Player {id} is not in game"),
+        }
+    }
+
+    pub fn of_wrong_turn(message: &str) -> Self {
+        ServerError {
+            status: 409,
+            message: format!("Wrong turn: {message}"),
+        }
+    }
+}
+
+impl Display for GameProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameProcessError::Bot(e) => write!(f, "bot error: {}", e.message),
+            GameProcessError::Hook(e) => write!(f, "hook error: {}", e.message),
+            GameProcessError::Core(e) => write!(f, "core error: {}", e.message),
+            GameProcessError::Interrupted => write!(f, "interrupted by shutdown"),
+        }
+    }
+}
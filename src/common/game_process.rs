@@ -1,11 +1,25 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::record::snapshot::GameSnapshot;
+
 use super::{
-    bot::{Bot, Cell, Coords, Field, Side, Size, Turn},
-    error::GameProcessError,
+    bot::{Analysis, Bot, Cell, Coords, Field, Side, Size, Turn},
+    error::{CoreError, GameProcessError},
     error_utils::ResultExt,
-    hooks::Hooks,
-    utils::Direction,
+    hooks::{Hooks, OpeningStep},
+    logging::{self, LogContext},
+    observer::GameObserver,
+    opening::{self, SideChoice, ADDED_STONES, PROPOSED_STONES},
+    rules::RuleSet,
+    session::{Shutdown, ShutdownPolicy},
 };
 
+static ANALYSIS_CANDIDATES: usize = 3;
+
 pub struct GameProcess {
     bot: Box<dyn Bot>,
     hooks: Box<dyn Hooks>,
@@ -13,12 +27,25 @@ pub struct GameProcess {
     field: Option<Field>,
     play_info: Option<PlayInfo>,
     history: Vec<Turn>,
+    pondering: bool,
     bot_prediction: Option<Turn>,
+    /// How the bot came to `bot_prediction`, told to observers once it's made.
+    bot_thinking: Option<(Duration, Option<Analysis>)>,
+    observers: Vec<Box<dyn GameObserver>>,
+    shutdown: Shutdown,
+    shutdown_policy: ShutdownPolicy,
+    snapshots_dir: Option<PathBuf>,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum GameState {
     Initial,
+    Resuming,
+    /// Opening rules: placing the opening stones, then waiting for the opponent
+    /// to choose a side or to add stones, or choosing a side ourselves.
+    OpeningProposing,
+    OpeningWaiting,
+    SideChoosing,
     TurnWaiting,
     BotThinking,
     TurnMaking,
@@ -32,10 +59,16 @@ pub enum GameResult {
     Draw,
 }
 
+#[derive(Clone)]
 pub struct PlayInfo {
     pub field_size: Size,
     pub win_condition: usize,
+    pub rules: RuleSet,
+    /// With opening rules: X for the player placing the opening stones, until
+    /// the sides are chosen.
     pub side: Side,
+    pub player_id: Option<String>,
+    pub game_id: Option<String>,
 }
 
 impl GameProcess {
@@ -47,10 +80,55 @@ impl GameProcess {
             field: None,
             play_info: None,
             history: Default::default(),
+            pondering: false,
             bot_prediction: None,
+            bot_thinking: None,
+            observers: vec![],
+            shutdown: Shutdown::default(),
+            shutdown_policy: ShutdownPolicy::default(),
+            snapshots_dir: None,
         }
     }
 
+    /// Continues a game from its snapshot. The hooks are reattached to the game
+    /// and the position is synced with theirs before the first turn.
+    pub fn resume(
+        bot: Box<dyn Bot>,
+        hooks: Box<dyn Hooks>,
+        snapshot: &GameSnapshot,
+    ) -> Result<Self, GameProcessError> {
+        let mut process = GameProcess::new(bot, hooks);
+        process.play_info = Some(snapshot.play_info().pack_err()?);
+        process.field = Some(snapshot.field().pack_err()?);
+        process.history = snapshot.turns();
+        process.bot_prediction = snapshot.bot_prediction();
+        process.state = GameState::Resuming;
+
+        Ok(process)
+    }
+
+    pub fn with_pondering(mut self, pondering: bool) -> Self {
+        self.pondering = pondering;
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Box<dyn GameObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    pub fn with_shutdown(mut self, shutdown: Shutdown, policy: ShutdownPolicy) -> Self {
+        self.shutdown = shutdown;
+        self.shutdown_policy = policy;
+        self
+    }
+
+    /// Keeps a snapshot of the game in `snapshots_dir` after every step.
+    pub fn with_snapshots_dir(mut self, snapshots_dir: PathBuf) -> Self {
+        self.snapshots_dir = Some(snapshots_dir);
+        self
+    }
+
     pub fn run(&mut self) -> Result<GameResult, GameProcessError> {
         loop {
             match &self.state {
@@ -64,38 +142,212 @@ impl GameProcess {
 
     pub fn run_step(&mut self) -> Result<GameState, GameProcessError> {
         let old_state = self.state;
+        logging::set_context(self.log_context());
 
-        self.state = match self.state {
-            GameState::Initial => self.run_initial_state()?,
-            GameState::TurnWaiting => self.run_turn_waiting_state()?,
-            GameState::BotThinking => self.run_bot_thinking_state()?,
-            GameState::TurnMaking => self.run_turn_making_state()?,
-            GameState::Finished(_) => self.state,
+        let step = match self.state {
+            GameState::Initial => self.run_initial_state(),
+            GameState::Finished(_) => Ok(self.state),
+            _ if self.is_resigning() => {
+                logging::warning!("resigning the game on shutdown");
+                Ok(GameState::Finished(GameResult::Defeat))
+            }
+            GameState::Resuming => self.run_resuming_state().inspect_err(|_| {
+                logging::error!("can't resume the game, dropping its snapshot");
+                self.remove_snapshot();
+            }),
+            GameState::OpeningProposing => self.run_opening_proposing_state(),
+            GameState::OpeningWaiting => self.run_opening_waiting_state(),
+            GameState::SideChoosing => self.run_side_choosing_state(),
+            GameState::TurnWaiting => self.run_turn_waiting_state(),
+            GameState::BotThinking => self.run_bot_thinking_state(),
+            GameState::TurnMaking => self.run_turn_making_state(),
         };
+        self.state = match step {
+            Err(_) if self.shutdown.is_requested() && matches!(old_state, GameState::Initial) => {
+                logging::info!("stopped waiting for a game on shutdown");
+                return Err(GameProcessError::Interrupted);
+            }
+            Err(_) if self.is_resigning() => {
+                logging::warning!("resigning the game on shutdown");
+                GameState::Finished(GameResult::Defeat)
+            }
+            step => step.inspect_err(|e| {
+                logging::error!("game process failed: {e}");
+                self.notify(|observer| observer.on_error(e));
+            })?,
+        };
+
+        logging::set_context(self.log_context());
+        logging::debug!("game process state {:?} -> {:?}", old_state, self.state);
+        self.save_snapshot();
+        let new_state = self.state;
+        self.notify(|observer| observer.on_state_changed(old_state, new_state));
+        if let GameState::Finished(result) = self.state {
+            if !matches!(old_state, GameState::Finished(_)) {
+                logging::info!("game finished: {result:?}");
+                self.notify(|observer| observer.on_game_finished(result));
+            }
+        }
 
-        println!("game process state {:?} -> {:?}", old_state, self.state);
         Ok(self.state)
     }
 
     fn run_initial_state(&mut self) -> Result<GameState, GameProcessError> {
         let play_info = self.hooks.init().pack_err()?;
-        self.field = Some(Field::new(play_info.field_size));
+        self.bot.start_game(&play_info).pack_err()?;
+        self.field = Some(play_info.rules.new_field(play_info.field_size));
+        self.notify(|observer| observer.on_game_started(&play_info));
+        let next_state = match (play_info.rules.opening, play_info.side) {
+            (None, _) => GameState::TurnWaiting,
+            (Some(_), Side::X) => GameState::OpeningProposing,
+            (Some(_), Side::O) => GameState::OpeningWaiting,
+        };
         self.play_info = Some(play_info);
 
-        Ok(GameState::TurnWaiting)
+        Ok(next_state)
+    }
+
+    fn run_resuming_state(&mut self) -> Result<GameState, GameProcessError> {
+        let play_info = self.play_info.clone().unwrap();
+        let status = self.hooks.reattach(&play_info).pack_err()?;
+        self.bot.start_game(&play_info).pack_err()?;
+
+        // moves made while we were away: maybe our own, then the opponent's
+        let field = self.field.as_mut().unwrap();
+        for side in [play_info.side, play_info.side.opposite()] {
+            for turn in status.field.stones() {
+                let missed =
+                    turn.side == side && matches!(field.get(turn.coords), Some(Cell::Empty));
+                if missed {
+                    field.add_turn(&turn).pack_err()?;
+                    self.history.push(turn);
+                }
+            }
+        }
+        logging::info!("resumed the game after {} moves", self.history.len());
+        let history = self.history.clone();
+        self.notify(|observer| observer.on_game_resumed(&play_info, &history));
+
+        let field = self.field.as_ref().unwrap();
+        let next_state = match status.next_side {
+            None => {
+                let winner = play_info.rules.winner(field, play_info.win_condition);
+                GameState::Finished(self.result_for(winner))
+            }
+            Some(side) if side != play_info.side => GameState::TurnWaiting,
+            // the turn chosen before the crash, if it hasn't been made yet
+            Some(_) => match self.bot_prediction {
+                Some(turn)
+                    if play_info
+                        .rules
+                        .is_legal(field, &turn, play_info.win_condition) =>
+                {
+                    GameState::TurnMaking
+                }
+                _ => GameState::BotThinking,
+            },
+        };
+
+        Ok(next_state)
+    }
+
+    fn run_opening_proposing_state(&mut self) -> Result<GameState, GameProcessError> {
+        let thinking_started = Instant::now();
+        let field = self.field.as_ref().unwrap();
+        let stones = self
+            .bot
+            .propose_opening(field, PROPOSED_STONES)
+            .pack_err()?;
+        logging::info!("bot proposed opening {}", algebraic(&stones));
+        self.hooks.propose_opening(&stones).pack_err()?;
+        let think_time = thinking_started.elapsed();
+        for turn in self.place_opening(&stones, PROPOSED_STONES)? {
+            self.notify(|observer| observer.on_bot_turn(&turn, think_time, None));
+        }
+
+        Ok(GameState::OpeningWaiting)
+    }
+
+    fn run_opening_waiting_state(&mut self) -> Result<GameState, GameProcessError> {
+        let waiting_started = Instant::now();
+
+        match self.hooks.wait_for_opening().pack_err()? {
+            OpeningStep::Stones(updated) => {
+                let stones = opening::new_stones(self.field.as_ref().unwrap(), &updated);
+                logging::info!("opponent placed opening stones {}", algebraic(&stones));
+                let count = match self.history.is_empty() {
+                    true => PROPOSED_STONES,
+                    false => ADDED_STONES,
+                };
+                let waited = waiting_started.elapsed();
+                for turn in self.place_opening(&stones, count)? {
+                    self.notify(|observer| observer.on_opponent_turn(&turn, waited));
+                }
+
+                Ok(GameState::SideChoosing)
+            }
+            OpeningStep::SideTaken(side) => {
+                logging::info!("opponent took {side:?}");
+                self.settle_side(side.opposite())
+            }
+        }
+    }
+
+    fn run_side_choosing_state(&mut self) -> Result<GameState, GameProcessError> {
+        let thinking_started = Instant::now();
+        let field = self.field.as_ref().unwrap();
+        let opening = self.play_info.as_ref().unwrap().rules.opening.unwrap();
+        let can_add_stones = opening.can_add_stones(self.history.len());
+        let choice = self.bot.choose_side(field, can_add_stones).pack_err()?;
+        if matches!(choice, SideChoice::AddStones(_)) && !can_add_stones {
+            return Err(CoreError::of_opening("no more stones may be added")).pack_err();
+        }
+        self.hooks.choose_side(&choice).pack_err()?;
+
+        match choice {
+            SideChoice::Take(side) => {
+                logging::info!("bot took {side:?}");
+                self.settle_side(side)
+            }
+            SideChoice::AddStones(stones) => {
+                logging::info!("bot added opening stones {}", algebraic(&stones));
+                let think_time = thinking_started.elapsed();
+                for turn in self.place_opening(&stones, ADDED_STONES)? {
+                    self.notify(|observer| observer.on_bot_turn(&turn, think_time, None));
+                }
+
+                Ok(GameState::OpeningWaiting)
+            }
+        }
     }
 
     fn run_turn_waiting_state(&mut self) -> Result<GameState, GameProcessError> {
-        let enemy_turn = self.hooks.wait_for_turn().pack_err()?;
+        let waiting_started = Instant::now();
+        if self.pondering {
+            let side = self.play_info.as_ref().unwrap().side;
+            self.bot
+                .start_pondering(self.field.as_ref().unwrap(), side)
+                .pack_err()?;
+        }
+        let enemy_turn = self.hooks.wait_for_turn();
+        if self.pondering {
+            self.bot.stop_pondering();
+        }
+        let enemy_turn = enemy_turn.pack_err()?;
 
         match enemy_turn {
             None => Ok(GameState::BotThinking),
             Some(enemy_turn) => {
-                self.field.as_mut().unwrap().add_turn(&enemy_turn).pack_err()?;
+                logging::info!("opponent made turn {}", enemy_turn.coords.to_algebraic());
+                let enemy_turn = self.place(&enemy_turn)?;
+                let waited = waiting_started.elapsed();
+                self.history.push(enemy_turn);
+                self.notify(|observer| observer.on_opponent_turn(&enemy_turn, waited));
 
-                let next_state = if self.check_win(&enemy_turn) {
-                    GameState::Finished(GameResult::Defeat)
-                } else if self.is_field_full() {
+                let winner = self.winner_after(&enemy_turn);
+                let next_state = if winner.is_some() {
+                    GameState::Finished(self.result_for(winner))
+                } else if self.is_exhausted() {
                     GameState::Finished(GameResult::Draw)
                 } else {
                     GameState::BotThinking
@@ -107,23 +359,48 @@ impl GameProcess {
     }
 
     fn run_bot_thinking_state(&mut self) -> Result<GameState, GameProcessError> {
-        let best_turn = self
+        let thinking_started = Instant::now();
+        let field = self.field.as_ref().unwrap();
+        let side = self.play_info.as_ref().unwrap().side;
+        let analysis = self
             .bot
-            .make_turn(self.field.as_ref().unwrap(), self.play_info.as_ref().unwrap().side)
+            .analyze(field, side, ANALYSIS_CANDIDATES)
             .pack_err()?;
+        let best_turn = match &analysis {
+            Some(analysis) => {
+                logging::debug!("bot analysis: {analysis}");
+                Turn {
+                    coords: analysis.candidates[0].coords,
+                    side,
+                }
+            }
+            None => self.bot.make_turn(field, side).pack_err()?,
+        };
+        let think_time = thinking_started.elapsed();
+        logging::info!(
+            "bot chose {} in {} ms",
+            best_turn.coords.to_algebraic(),
+            think_time.as_millis()
+        );
         self.bot_prediction = Some(best_turn);
+        self.bot_thinking = Some((think_time, analysis));
 
         Ok(GameState::TurnMaking)
     }
 
     fn run_turn_making_state(&mut self) -> Result<GameState, GameProcessError> {
-        let best_turn = self.bot_prediction.as_ref().unwrap();
+        let best_turn = self.bot_prediction.unwrap();
         self.hooks.make_turn(&best_turn.coords).pack_err()?;
-        self.field.as_mut().unwrap().add_turn(best_turn).pack_err()?;
+        let best_turn = self.place(&best_turn)?;
+        self.history.push(best_turn);
+        // a turn resumed from a snapshot was thought over before the restart
+        let (think_time, analysis) = self.bot_thinking.take().unwrap_or_default();
+        self.notify(|observer| observer.on_bot_turn(&best_turn, think_time, analysis.as_ref()));
 
-        let next_state = if self.check_win(best_turn) {
-            GameState::Finished(GameResult::Win)
-        } else if self.is_field_full() {
+        let winner = self.winner_after(&best_turn);
+        let next_state = if winner.is_some() {
+            GameState::Finished(self.result_for(winner))
+        } else if self.is_exhausted() {
             GameState::Finished(GameResult::Draw)
         } else {
             GameState::TurnWaiting
@@ -132,75 +409,430 @@ impl GameProcess {
         Ok(next_state)
     }
 
-    fn check_win(&self, turn: &Turn) -> bool {
-        let field_size = self.play_info.as_ref().unwrap().field_size;
-        let win_condition = self.play_info.as_ref().unwrap().win_condition;
+    fn save_snapshot(&self) {
+        let (Some(snapshots_dir), Some(play_info), Some(field)) =
+            (&self.snapshots_dir, &self.play_info, &self.field)
+        else {
+            return;
+        };
 
-        for i in 0..=(field_size.width - win_condition) {
-            for j in 0..field_size.height {
-                let start = Coords { x: i, y: j };
-                let direction = Direction { x: 1, y: 0 };
-                if self.check_win_from(turn, start, direction) {
-                    return true;
+        let snapshot = GameSnapshot::new(
+            play_info,
+            field,
+            &self.history,
+            self.state,
+            self.bot_prediction,
+        );
+        match snapshot {
+            Some(snapshot) => {
+                if let Err(e) = snapshot.save(snapshots_dir) {
+                    logging::error!("can't save game snapshot: {e}");
                 }
             }
+            None => self.remove_snapshot(),
         }
+    }
 
-        for i in 0..field_size.width {
-            for j in 0..=(field_size.height - win_condition) {
-                let start = Coords { x: i, y: j };
-                let direction = Direction { x: 0, y: 1 };
-                if self.check_win_from(turn, start, direction) {
-                    return true;
-                }
-            }
+    fn remove_snapshot(&self) {
+        let (Some(snapshots_dir), Some(play_info)) = (&self.snapshots_dir, &self.play_info) else {
+            return;
+        };
+
+        if let Some(player_id) = &play_info.player_id {
+            let _ = fs::remove_file(GameSnapshot::path(snapshots_dir, player_id));
         }
+    }
 
-        for i in 0..=(field_size.width - win_condition) {
-            for j in 0..=(field_size.height - win_condition) {
-                let start = Coords { x: i, y: j };
-                let direction = Direction { x: 1, y: 0 };
-                if self.check_win_from(turn, start, direction) {
-                    return true;
-                }
-            }
+    fn is_resigning(&self) -> bool {
+        self.shutdown_policy == ShutdownPolicy::Resign && self.shutdown.is_requested()
+    }
+
+    fn notify(&mut self, event: impl Fn(&mut dyn GameObserver)) {
+        for observer in &mut self.observers {
+            event(observer.as_mut());
         }
+    }
+
+    fn log_context(&self) -> LogContext {
+        let play_info = self.play_info.as_ref();
+        let move_number = match self.state {
+            GameState::Finished(_) => self.history.len(),
+            _ => self.history.len() + 1,
+        };
 
-        false
+        LogContext {
+            player_id: play_info.and_then(|info| info.player_id.clone()),
+            game_id: play_info.and_then(|info| info.game_id.clone()),
+            side: play_info.map(|info| info.side),
+            move_number: play_info.map(|_| move_number),
+        }
     }
 
-    fn check_win_from(&self, turn: &Turn, start: Coords, dir: Direction) -> bool {
+    fn place_opening(
+        &mut self,
+        stones: &[Coords],
+        count: usize,
+    ) -> Result<Vec<Turn>, GameProcessError> {
+        let play_info = self.play_info.as_ref().unwrap();
+        let field = self.field.as_mut().unwrap();
+        let turns = opening::place_stones(
+            field,
+            stones,
+            count,
+            play_info.rules,
+            play_info.win_condition,
+        )
+        .pack_err()?;
+        self.history.extend(&turns);
+
+        Ok(turns)
+    }
+
+    /// Starts the game proper once the opening decided which side we play.
+    fn settle_side(&mut self, side: Side) -> Result<GameState, GameProcessError> {
+        let play_info = self.play_info.as_mut().unwrap();
+        play_info.side = side;
+        self.bot.start_game(play_info).pack_err()?;
+        self.notify(|observer| observer.on_side_chosen(side));
+
+        match opening::side_to_move(self.field.as_ref().unwrap()) == side {
+            true => Ok(GameState::BotThinking),
+            false => Ok(GameState::TurnWaiting),
+        }
+    }
+
+    /// Makes `turn` on the field by the rules of the game, see `RuleSet::place`.
+    fn place(&mut self, turn: &Turn) -> Result<Turn, GameProcessError> {
+        let play_info = self.play_info.as_ref().unwrap();
+
+        play_info
+            .rules
+            .place(self.field.as_mut().unwrap(), turn, play_info.win_condition)
+            .pack_err()
+    }
+
+    fn winner_after(&self, turn: &Turn) -> Option<Side> {
+        let play_info = self.play_info.as_ref().unwrap();
+
+        play_info
+            .rules
+            .winner_after(self.field.as_ref().unwrap(), turn, play_info.win_condition)
+    }
+
+    fn result_for(&self, winner: Option<Side>) -> GameResult {
+        match winner {
+            Some(side) if side == self.play_info.as_ref().unwrap().side => GameResult::Win,
+            Some(_) => GameResult::Defeat,
+            None => GameResult::Draw,
+        }
+    }
+
+    /// Whether no turns are left, see `RuleSet::is_exhausted`.
+    fn is_exhausted(&self) -> bool {
+        let play_info = self.play_info.as_ref().unwrap();
         let field = self.field.as_ref().unwrap();
-        let win_condition = self.play_info.as_ref().unwrap().win_condition;
-        let mut in_a_row = 0;
-        let mut k = 0;
+        let field_size = play_info.field_size;
 
-        loop {
-            match field.get(start + dir * k) {
-                Some(cell) => match cell {
-                    Cell::Value(cell_side) => {
-                        if cell_side == turn.side {
-                            in_a_row += 1;
-                        } else {
-                            in_a_row = 0;
-                        }
-                    }
-                    Cell::Empty => in_a_row = 0,
+        match field.is_infinite() || play_info.rules.ultimate {
+            true => play_info.rules.is_exhausted(field),
+            false => self.history.len() >= field_size.width * field_size.height,
+        }
+    }
+}
+
+fn algebraic(stones: &[Coords]) -> String {
+    let names: Vec<String> = stones.iter().map(|coords| coords.to_algebraic()).collect();
+
+    names.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::common::{
+        bot::Analysis,
+        error::{BotError, HookError},
+        hooks::GameStatus,
+    };
+
+    use super::*;
+
+    struct FirstEmptyBot;
+
+    impl Bot for FirstEmptyBot {
+        fn calculate_best_turn(&self, field: &Field, _side: Side) -> Result<Coords, BotError> {
+            (0..field.size.height as isize)
+                .flat_map(|y| (0..field.size.width as isize).map(move |x| Coords { x, y }))
+                .find(|&coords| matches!(field.get(coords), Some(Cell::Empty)))
+                .ok_or_else(BotError::of_no_moves)
+        }
+    }
+
+    #[derive(Default)]
+    struct ScriptedHooks {
+        opponent_turns: VecDeque<Option<Turn>>,
+        opening_steps: VecDeque<OpeningStep>,
+        rules: RuleSet,
+        server_field: Option<Field>,
+        rejects_turns: bool,
+    }
+
+    impl Hooks for ScriptedHooks {
+        fn init(&mut self) -> Result<PlayInfo, HookError> {
+            Ok(PlayInfo {
+                field_size: Size {
+                    width: 3,
+                    height: 3,
                 },
-                None => return false,
-            }
+                win_condition: 3,
+                rules: self.rules,
+                side: Side::X,
+                player_id: None,
+                game_id: None,
+            })
+        }
+
+        fn wait_for_turn(&mut self) -> Result<Option<Turn>, HookError> {
+            self.opponent_turns.pop_front().ok_or_else(|| HookError {
+                message: "no more turns".to_owned(),
+            })
+        }
 
-            if in_a_row >= win_condition {
-                return true;
+        fn make_turn(&mut self, _turn: &Coords) -> Result<(), HookError> {
+            match self.rejects_turns {
+                true => Err(HookError {
+                    message: "turn rejected".to_owned(),
+                }),
+                false => Ok(()),
             }
+        }
+
+        fn propose_opening(&mut self, _stones: &[Coords]) -> Result<(), HookError> {
+            Ok(())
+        }
+
+        fn wait_for_opening(&mut self) -> Result<OpeningStep, HookError> {
+            self.opening_steps.pop_front().ok_or_else(|| HookError {
+                message: "no more opening steps".to_owned(),
+            })
+        }
 
-            k += 1;
+        fn reattach(&mut self, _play_info: &PlayInfo) -> Result<GameStatus, HookError> {
+            Ok(GameStatus {
+                field: self.server_field.clone().unwrap(),
+                next_side: Some(Side::X),
+            })
         }
     }
 
-    fn is_field_full(&self) -> bool {
-        let field_size = self.play_info.as_ref().unwrap().field_size;
+    struct EventLog(Arc<Mutex<Vec<String>>>);
+
+    impl GameObserver for EventLog {
+        fn on_game_started(&mut self, play_info: &PlayInfo) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("started {:?}", play_info.side));
+        }
+
+        fn on_game_resumed(&mut self, _play_info: &PlayInfo, history: &[Turn]) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("resumed after {}", history.len()));
+        }
+
+        fn on_side_chosen(&mut self, side: Side) {
+            self.0.lock().unwrap().push(format!("side {side:?}"));
+        }
+
+        fn on_opponent_turn(&mut self, turn: &Turn, _waited: Duration) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("opponent {}", turn.coords.to_algebraic()));
+        }
+
+        fn on_bot_turn(&mut self, turn: &Turn, _time: Duration, _analysis: Option<&Analysis>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("bot {}", turn.coords.to_algebraic()));
+        }
+
+        fn on_error(&mut self, error: &GameProcessError) {
+            self.0.lock().unwrap().push(format!("error {error}"));
+        }
+
+        fn on_game_finished(&mut self, result: GameResult) {
+            self.0.lock().unwrap().push(format!("finished {result:?}"));
+        }
+    }
+
+    fn opponent(x: isize, y: isize) -> Option<Turn> {
+        Some(Turn {
+            coords: Coords { x, y },
+            side: Side::O,
+        })
+    }
+
+    fn game_process(
+        opponent_turns: Vec<Option<Turn>>,
+        events: &Arc<Mutex<Vec<String>>>,
+    ) -> GameProcess {
+        let hooks = ScriptedHooks {
+            opponent_turns: opponent_turns.into(),
+            ..Default::default()
+        };
+
+        GameProcess::new(Box::new(FirstEmptyBot), Box::new(hooks))
+            .with_observer(Box::new(EventLog(events.clone())))
+    }
+
+    #[test]
+    fn observer_receives_game_events() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut process = game_process(vec![None, opponent(0, 1), opponent(1, 1)], &events);
+
+        assert!(matches!(process.run(), Ok(GameResult::Win)));
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "started X",
+                "bot a1",
+                "opponent a2",
+                "bot b1",
+                "opponent b2",
+                "bot c1",
+                "finished Win"
+            ]
+        );
+    }
+
+    #[test]
+    fn observer_misses_rejected_bot_turn() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let hooks = ScriptedHooks {
+            opponent_turns: vec![None].into(),
+            rejects_turns: true,
+            ..Default::default()
+        };
+        let mut process = GameProcess::new(Box::new(FirstEmptyBot), Box::new(hooks))
+            .with_observer(Box::new(EventLog(events.clone())));
+
+        assert!(process.run().is_err());
+        let events = events.lock().unwrap();
+        assert_eq!(events[0], "started X");
+        assert!(events[1].starts_with("error"));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn resigns_on_shutdown() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let shutdown = Shutdown::default();
+        let mut process = game_process(vec![None], &events)
+            .with_shutdown(shutdown.clone(), ShutdownPolicy::Resign);
+        shutdown.request();
+
+        assert!(matches!(process.run(), Ok(GameResult::Defeat)));
+        assert_eq!(*events.lock().unwrap(), ["started X", "finished Defeat"]);
+    }
+
+    #[test]
+    fn resumes_from_snapshot_and_syncs_missed_moves() {
+        let turn = |x, y, side| Turn {
+            coords: Coords { x, y },
+            side,
+        };
+        let mut play_info = ScriptedHooks::default().init().unwrap();
+        play_info.player_id = Some("p1".to_owned());
+        let history = [turn(0, 0, Side::X), turn(0, 1, Side::O)];
+        let mut field = Field::new(play_info.field_size);
+        let mut server_field = Field::new(play_info.field_size);
+        for turn in &history {
+            field.add_turn(turn).unwrap();
+            server_field.add_turn(turn).unwrap();
+        }
+        // our b1 was sent before the crash and the opponent answered with b2
+        server_field.add_turn(&turn(1, 0, Side::X)).unwrap();
+        server_field.add_turn(&turn(1, 1, Side::O)).unwrap();
+        let prediction = Some(turn(1, 0, Side::X));
+        let snapshot = GameSnapshot::new(
+            &play_info,
+            &field,
+            &history,
+            GameState::TurnMaking,
+            prediction,
+        )
+        .unwrap();
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let hooks = ScriptedHooks {
+            server_field: Some(server_field),
+            ..Default::default()
+        };
+        let mut process = GameProcess::resume(Box::new(FirstEmptyBot), Box::new(hooks), &snapshot)
+            .unwrap()
+            .with_observer(Box::new(EventLog(events.clone())));
+
+        assert!(matches!(process.run(), Ok(GameResult::Win)));
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["resumed after 4", "bot c1", "finished Win"]
+        );
+    }
+
+    #[test]
+    fn plays_opening_before_turns() {
+        let x = |x, y| {
+            Some(Turn {
+                coords: Coords { x, y },
+                side: Side::X,
+            })
+        };
+        let hooks = ScriptedHooks {
+            opponent_turns: vec![x(1, 1), x(0, 2)].into(),
+            opening_steps: vec![OpeningStep::SideTaken(Side::X)].into(),
+            rules: RuleSet::parse("swap").unwrap(),
+            ..Default::default()
+        };
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut process = GameProcess::new(Box::new(FirstEmptyBot), Box::new(hooks))
+            .with_observer(Box::new(EventLog(events.clone())));
+
+        assert!(matches!(process.run(), Ok(GameResult::Defeat)));
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "started X",
+                "bot a1",
+                "bot b1",
+                "bot c1",
+                "side O",
+                "bot a2",
+                "opponent b2",
+                "bot c2",
+                "opponent a3",
+                "finished Defeat"
+            ]
+        );
+    }
+
+    #[test]
+    fn observer_receives_errors() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut process = game_process(vec![None], &events);
 
-        self.history.len() >= field_size.width * field_size.height
+        assert!(process.run().is_err());
+        assert_eq!(
+            events.lock().unwrap().last().unwrap(),
+            "error hook error: no more turns"
+        );
     }
 }
@@ -1,7 +1,47 @@
-use super::{bot::{Turn, Coords}, game_process::PlayInfo, error::HookError};
+use super::{
+    bot::{Coords, Field, Side, Turn},
+    error::HookError,
+    game_process::PlayInfo,
+    opening::SideChoice,
+};
 
 pub trait Hooks {
     fn init(&mut self) -> Result<PlayInfo, HookError>;
     fn wait_for_turn(&mut self) -> Result<Option<Turn>, HookError>;
     fn make_turn(&mut self, turn: &Coords) -> Result<(), HookError>;
+
+    /// Sends the opening stones we place under opening rules.
+    fn propose_opening(&mut self, _stones: &[Coords]) -> Result<(), HookError> {
+        Err(HookError::of_opening_unsupported())
+    }
+
+    /// Waits for the opponent's part of the opening.
+    fn wait_for_opening(&mut self) -> Result<OpeningStep, HookError> {
+        Err(HookError::of_opening_unsupported())
+    }
+
+    fn choose_side(&mut self, _choice: &SideChoice) -> Result<(), HookError> {
+        Err(HookError::of_opening_unsupported())
+    }
+
+    /// Attaches to a game joined earlier, e.g. by a crashed process,
+    /// without registering again.
+    fn reattach(&mut self, _play_info: &PlayInfo) -> Result<GameStatus, HookError> {
+        Err(HookError::of_reattach_unsupported())
+    }
+}
+
+/// What the opponent did in the opening.
+pub enum OpeningStep {
+    /// The field with the stones the opponent placed, the choice of side is ours now.
+    Stones(Field),
+    /// The side the opponent took, we play the other one.
+    SideTaken(Side),
+}
+
+/// Current state of a game as the server sees it.
+pub struct GameStatus {
+    pub field: Field,
+    /// Side to move, `None` once the game is finished.
+    pub next_side: Option<Side>,
 }
@@ -0,0 +1,268 @@
+//! Leveled logging with per-game context.
+//!
+//! Every thread carries its own [`LogContext`], so the lines of concurrent
+//! games can be told apart. Records go to stderr either as human readable
+//! text or as JSON lines:
+//!
+//! ```text
+//! 1700000000.123 INFO  [player=4f1c.. game=0 side=X move=5] bot chose h8 in 12 ms
+//! {"ts":1700000000123,"level":"INFO","player_id":"4f1c..","game_id":"0","side":"X","move":5,"message":"bot chose h8 in 12 ms"}
+//! ```
+//!
+//! Use the `error!`, `warning!`, `info!` and `debug!` macros to log.
+//! Level and format are taken from the `LOG_LEVEL` (`error`, `warn`, `info`,
+//! `debug`) and `LOG_FORMAT` (`text`, `json`) environment variables by
+//! [`init_from_env`].
+
+use std::{
+    cell::RefCell,
+    env, fmt,
+    io::{self, Write},
+    sync::atomic::{AtomicU8, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use super::bot::Side;
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static FORMAT: AtomicU8 = AtomicU8::new(LogFormat::Text as u8);
+
+thread_local! {
+    static CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogContext {
+    pub player_id: Option<String>,
+    pub game_id: Option<String>,
+    pub side: Option<Side>,
+    pub move_number: Option<usize>,
+}
+
+impl Level {
+    pub fn parse(value: &str) -> Option<Level> {
+        match value.to_ascii_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+
+    fn from_u8(value: u8) -> Level {
+        match value {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<LogFormat> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn init(level: Level, format: LogFormat) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn init_from_env() {
+    let level = env::var("LOG_LEVEL")
+        .ok()
+        .and_then(|value| Level::parse(&value));
+    let format = env::var("LOG_FORMAT")
+        .ok()
+        .and_then(|value| LogFormat::parse(&value));
+
+    init(
+        level.unwrap_or(Level::Info),
+        format.unwrap_or(LogFormat::Text),
+    );
+}
+
+pub fn enabled(level: Level) -> bool {
+    level <= Level::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+pub fn set_context(context: LogContext) {
+    CONTEXT.with(|current| *current.borrow_mut() = context);
+}
+
+pub fn context() -> LogContext {
+    CONTEXT.with(|current| current.borrow().clone())
+}
+
+pub fn log(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    let line = match FORMAT.load(Ordering::Relaxed) {
+        f if f == LogFormat::Json as u8 => format_json(level, &context(), args),
+        _ => format_text(level, &context(), args),
+    };
+    let _ = writeln!(io::stderr().lock(), "{line}");
+}
+
+pub fn flush() {
+    let _ = io::stderr().lock().flush();
+}
+
+fn format_text(level: Level, context: &LogContext, args: fmt::Arguments) -> String {
+    let now = unix_millis();
+    let mut fields = vec![];
+    if let Some(player_id) = &context.player_id {
+        fields.push(format!("player={player_id}"));
+    }
+    if let Some(game_id) = &context.game_id {
+        fields.push(format!("game={game_id}"));
+    }
+    if let Some(side) = context.side {
+        fields.push(format!("side={side:?}"));
+    }
+    if let Some(move_number) = context.move_number {
+        fields.push(format!("move={move_number}"));
+    }
+
+    let context = match fields.is_empty() {
+        true => String::new(),
+        false => format!("[{}] ", fields.join(" ")),
+    };
+    format!(
+        "{}.{:03} {:<5} {context}{args}",
+        now / 1000,
+        now % 1000,
+        level.name()
+    )
+}
+
+fn format_json(level: Level, context: &LogContext, args: fmt::Arguments) -> String {
+    let mut record = Map::new();
+    record.insert("ts".into(), json!(unix_millis()));
+    record.insert("level".into(), json!(level.name()));
+    if let Some(player_id) = &context.player_id {
+        record.insert("player_id".into(), json!(player_id));
+    }
+    if let Some(game_id) = &context.game_id {
+        record.insert("game_id".into(), json!(game_id));
+    }
+    if let Some(side) = context.side {
+        record.insert("side".into(), json!(format!("{side:?}")));
+    }
+    if let Some(move_number) = context.move_number {
+        record.insert("move".into(), json!(move_number));
+    }
+    record.insert("message".into(), json!(args.to_string()));
+
+    Value::Object(record).to_string()
+}
+
+fn unix_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or_default()
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::common::logging::log($crate::common::logging::Level::Error, format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::common::logging::log($crate::common::logging::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::common::logging::log($crate::common::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::common::logging::log($crate::common::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, error, info, warning};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_context() -> LogContext {
+        LogContext {
+            player_id: Some("p1".into()),
+            game_id: Some("7".into()),
+            side: Some(Side::O),
+            move_number: Some(4),
+        }
+    }
+
+    #[test]
+    fn text_line_carries_context() {
+        let line = format_text(Level::Warn, &game_context(), format_args!("hello {}", 1));
+
+        assert!(line.ends_with("WARN  [player=p1 game=7 side=O move=4] hello 1"));
+    }
+
+    #[test]
+    fn json_line_carries_context() {
+        let line = format_json(Level::Info, &game_context(), format_args!("hello"));
+        let record: Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(record["level"], "INFO");
+        assert_eq!(record["player_id"], "p1");
+        assert_eq!(record["game_id"], "7");
+        assert_eq!(record["side"], "O");
+        assert_eq!(record["move"], 4);
+        assert_eq!(record["message"], "hello");
+    }
+
+    #[test]
+    fn context_is_per_thread() {
+        set_context(game_context());
+        let other = std::thread::spawn(context).join().unwrap();
+
+        assert_eq!(context(), game_context());
+        assert_eq!(other, LogContext::default());
+    }
+}
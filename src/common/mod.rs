@@ -1,6 +1,16 @@
 pub mod bot;
+pub mod cube;
+pub mod cube_rules;
 pub mod game_process;
 pub mod hooks;
+pub mod logging;
+pub mod notation;
+pub mod observer;
+pub mod opening;
+pub mod renju;
+pub mod rules;
+pub mod session;
+pub mod ultimate;
 pub mod utils;
 pub mod error;
 pub(super) mod error_utils;
\ No newline at end of file
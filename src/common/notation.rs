@@ -0,0 +1,277 @@
+//! Text notation for positions and coordinates.
+//!
+//! Coordinates are algebraic: the column is a letter (`a` is `x = 0`, after `z`
+//! come `aa`, `ab`, ...) and the row is a one-based number, so `h8` is `x = 7, y = 7`.
+//!
+//! A position is `<width>x<height> <win_condition> <side to move> <rows>`, where
+//! rows go from `y = 0` and are separated by `/`. Inside a row `X` and `O` are
+//! stones and a number is a run of empty cells: `3x3 3 O 3/1X1/3`. The cell of
+//! the latest turn may follow, as ultimate rules need it: `3x3 3 O 3/1X1/3 b2`.
+
+use super::{
+    bot::{Cell, Coords, Field, Side, Size, Turn},
+    error::CoreError,
+};
+
+pub struct Position {
+    pub field: Field,
+    pub win_condition: usize,
+    pub side: Side,
+}
+
+impl Field {
+    pub fn to_notation(&self, win_condition: usize, side: Side) -> String {
+        let rows: Vec<String> = (0..self.size.height)
+            .map(|y| {
+                let mut row = String::new();
+                let mut empty = 0;
+                for x in 0..self.size.width {
+                    match self.geti(x, y) {
+                        Some(Cell::Value(side)) => {
+                            if empty > 0 {
+                                row += &empty.to_string();
+                                empty = 0;
+                            }
+                            row += side_symbol(side);
+                        }
+                        _ => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    row += &empty.to_string();
+                }
+                row
+            })
+            .collect();
+
+        let last_move = self
+            .last_move()
+            .map(|coords| format!(" {}", coords.to_algebraic()))
+            .unwrap_or_default();
+
+        format!(
+            "{}x{} {} {} {}{last_move}",
+            self.size.width,
+            self.size.height,
+            win_condition,
+            side_symbol(side),
+            rows.join("/")
+        )
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Position, CoreError> {
+        let wrong = || CoreError::of_notation(notation);
+        let parts: Vec<&str> = notation.split_whitespace().collect();
+        let (size, win_condition, side, rows, last_move) = match parts[..] {
+            [size, win_condition, side, rows] => (size, win_condition, side, rows, None),
+            [size, win_condition, side, rows, last_move] => {
+                let last_move = Coords::from_algebraic(last_move).map_err(|_| wrong())?;
+                (size, win_condition, side, rows, Some(last_move))
+            }
+            _ => return Err(wrong()),
+        };
+
+        let (width, height) = size.split_once('x').ok_or_else(wrong)?;
+        let size = Size {
+            width: width.parse().map_err(|_| wrong())?,
+            height: height.parse().map_err(|_| wrong())?,
+        };
+        let win_condition = win_condition.parse().map_err(|_| wrong())?;
+        let side = parse_side(side).ok_or_else(wrong)?;
+
+        let rows: Vec<&str> = rows.split('/').collect();
+        if rows.len() != size.height || size.width.checked_mul(size.height).is_none() {
+            return Err(wrong());
+        }
+
+        let mut stones = vec![];
+        for (y, row) in rows.into_iter().enumerate() {
+            let mut x: usize = 0;
+            let mut empty = String::new();
+            for c in row.chars().chain(Some(' ')) {
+                if c.is_ascii_digit() {
+                    empty.push(c);
+                    continue;
+                }
+                if !empty.is_empty() {
+                    let run = empty.parse::<usize>().map_err(|_| wrong())?;
+                    x = x
+                        .checked_add(run)
+                        .filter(|&x| x <= size.width)
+                        .ok_or_else(wrong)?;
+                    empty.clear();
+                }
+                if c == ' ' {
+                    break;
+                }
+
+                let side = parse_side(&c.to_string()).ok_or_else(wrong)?;
+                stones.push(Turn {
+                    coords: Coords {
+                        x: x as isize,
+                        y: y as isize,
+                    },
+                    side,
+                });
+                x += 1;
+            }
+            if x != size.width {
+                return Err(wrong());
+            }
+        }
+        let field = Field::new(size).with_stones(&stones, last_move)?;
+        if field.last_move() != last_move {
+            return Err(wrong());
+        }
+
+        Ok(Position {
+            field,
+            win_condition,
+            side,
+        })
+    }
+}
+
+impl Coords {
+    /// Like `e5`, or `x,y` for the cells of infinite fields left of or below `a1`.
+    pub fn to_algebraic(self) -> String {
+        match self.x >= 0 && self.y >= 0 {
+            true => format!("{}{}", column_name(self.x as usize), self.y + 1),
+            false => format!("{},{}", self.x, self.y),
+        }
+    }
+
+    pub fn from_algebraic(algebraic: &str) -> Result<Coords, CoreError> {
+        let wrong = || CoreError::of_notation(algebraic);
+        if let Some((x, y)) = algebraic.split_once(',') {
+            return Ok(Coords {
+                x: x.parse().map_err(|_| wrong())?,
+                y: y.parse().map_err(|_| wrong())?,
+            });
+        }
+        let digits = algebraic
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(wrong)?;
+        let (column, row) = algebraic.split_at(digits);
+        if column.is_empty() || !column.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(wrong());
+        }
+
+        let x = column
+            .bytes()
+            .fold(0, |x, c| x * 26 + (c - b'a') as usize + 1);
+        let y: usize = row.parse().map_err(|_| wrong())?;
+        if y == 0 {
+            return Err(wrong());
+        }
+
+        Ok(Coords {
+            x: x as isize - 1,
+            y: y as isize - 1,
+        })
+    }
+}
+
+pub fn column_name(x: usize) -> String {
+    let mut column = String::new();
+    let mut x = x + 1;
+    while x > 0 {
+        column.insert(0, (b'a' + ((x - 1) % 26) as u8) as char);
+        x = (x - 1) / 26;
+    }
+
+    column
+}
+
+fn side_symbol(side: Side) -> &'static str {
+    match side {
+        Side::X => "X",
+        Side::O => "O",
+    }
+}
+
+fn parse_side(symbol: &str) -> Option<Side> {
+    match symbol {
+        "X" => Some(Side::X),
+        "O" => Some(Side::O),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::bot::{Cell, Coords, Field, Side};
+
+    #[test]
+    fn algebraic_coords_round_trip() {
+        let cases = [
+            (Coords { x: 0, y: 0 }, "a1"),
+            (Coords { x: 7, y: 7 }, "h8"),
+            (Coords { x: 25, y: 9 }, "z10"),
+            (Coords { x: 26, y: 0 }, "aa1"),
+            (Coords { x: 52, y: 99 }, "ba100"),
+            (Coords { x: -3, y: 4 }, "-3,4"),
+            (Coords { x: 2, y: -1 }, "2,-1"),
+        ];
+
+        for (coords, algebraic) in cases {
+            assert_eq!(coords.to_algebraic(), algebraic);
+            assert_eq!(Coords::from_algebraic(algebraic).unwrap(), coords);
+        }
+    }
+
+    #[test]
+    fn wrong_algebraic_coords_are_rejected() {
+        for algebraic in ["", "a", "8", "a0", "H8", "h8x", "-h8"] {
+            assert!(Coords::from_algebraic(algebraic).is_err(), "{algebraic}");
+        }
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let notation = "4x3 3 O 4/1X2/O3";
+        let position = Field::from_notation(notation).unwrap();
+
+        assert_eq!(position.field.size.width, 4);
+        assert_eq!(position.field.size.height, 3);
+        assert_eq!(position.win_condition, 3);
+        assert_eq!(position.side, Side::O);
+        assert!(matches!(
+            position.field.geti(1, 1),
+            Some(Cell::Value(Side::X))
+        ));
+        assert!(matches!(
+            position.field.geti(0, 2),
+            Some(Cell::Value(Side::O))
+        ));
+        assert_eq!(position.field.to_notation(3, Side::O), notation);
+        assert_eq!(position.field.last_move(), None);
+    }
+
+    #[test]
+    fn keeps_last_move() {
+        let notation = "3x3 3 X XO1/3/3 b1";
+        let position = Field::from_notation(notation).unwrap();
+
+        assert_eq!(position.field.last_move(), Some(Coords { x: 1, y: 0 }));
+        assert_eq!(position.field.to_notation(3, Side::X), notation);
+        assert!(Field::from_notation("3x3 3 X XO1/3/3 c1").is_err());
+    }
+
+    #[test]
+    fn wrong_positions_are_rejected() {
+        for notation in [
+            "",
+            "3x3 3 X 3/3",
+            "3x3 3 X 3/3/4",
+            "3x3 3 Z 3/3/3",
+            "3x3 3 X 3/1Y1/3",
+            "3x3 three X 3/3/3",
+            "3x1 3 X 2X18446744073709551615",
+            "3x1 3 X 18446744073709551615X",
+            "18446744073709551615x2 3 X 1/1",
+        ] {
+            assert!(Field::from_notation(notation).is_err(), "{notation}");
+        }
+    }
+}
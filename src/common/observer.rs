@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use super::{
+    bot::{Analysis, Side, Turn},
+    error::GameProcessError,
+    game_process::{GameResult, GameState, PlayInfo},
+};
+
+/// Receives events of a running `GameProcess`.
+///
+/// All methods do nothing by default, so observers implement only what they need.
+pub trait GameObserver {
+    fn on_game_started(&mut self, _play_info: &PlayInfo) {}
+
+    /// Called instead of `on_game_started` when a game is resumed from a snapshot,
+    /// `history` holds the moves made before.
+    fn on_game_resumed(&mut self, _play_info: &PlayInfo, _history: &[Turn]) {}
+
+    /// Called when opening rules settled the side we play.
+    fn on_side_chosen(&mut self, _side: Side) {}
+
+    fn on_state_changed(&mut self, _from: GameState, _to: GameState) {}
+
+    /// `waited` is the time between our previous turn and the opponent's one.
+    fn on_opponent_turn(&mut self, _turn: &Turn, _waited: Duration) {}
+
+    fn on_bot_turn(&mut self, _turn: &Turn, _think_time: Duration, _analysis: Option<&Analysis>) {}
+
+    fn on_error(&mut self, _error: &GameProcessError) {}
+
+    fn on_game_finished(&mut self, _result: GameResult) {}
+}
@@ -0,0 +1,162 @@
+//! Opening rules where the first player proposes the opening stones and the
+//! sides are chosen afterwards.
+//!
+//! Swap: the first player places two X stones and one O stone, then the second
+//! one picks a side. Swap2: the second player may instead add one O and one X
+//! stone and leave the choice to the first player. Opening stones are placed
+//! alternately starting with X, so they read as regular moves and the game goes
+//! on with O to move.
+
+use std::fmt::Display;
+
+use super::{
+    bot::{Cell, Coords, Field, Side, Turn},
+    error::CoreError,
+    rules::RuleSet,
+};
+
+pub static PROPOSED_STONES: usize = 3;
+pub static ADDED_STONES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opening {
+    Swap,
+    Swap2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SideChoice {
+    Take(Side),
+    /// Swap2 only: places `ADDED_STONES` more and hands the choice back.
+    AddStones(Vec<Coords>),
+}
+
+impl Opening {
+    /// Whether the side may be left to the opponent once `stones` are on the field.
+    pub fn can_add_stones(&self, stones: usize) -> bool {
+        *self == Opening::Swap2 && stones == PROPOSED_STONES
+    }
+}
+
+/// Places exactly `count` opening stones alternately, starting with the side to move.
+pub fn place_stones(
+    field: &mut Field,
+    stones: &[Coords],
+    count: usize,
+    rules: RuleSet,
+    win_condition: usize,
+) -> Result<Vec<Turn>, CoreError> {
+    if stones.len() != count {
+        return Err(CoreError::of_opening(&format!(
+            "expected {count} stones, got {}",
+            stones.len()
+        )));
+    }
+
+    let mut turns = vec![];
+    for &coords in stones {
+        let turn = Turn {
+            coords,
+            side: side_to_move(field),
+        };
+        turns.push(rules.place(field, &turn, win_condition)?);
+    }
+    if rules.winner(field, win_condition).is_some() {
+        return Err(CoreError::of_opening("the stones decide the game"));
+    }
+
+    Ok(turns)
+}
+
+/// Stones on `updated` missing from `field`, in the order they are placed in.
+pub fn new_stones(field: &Field, updated: &Field) -> Vec<Coords> {
+    let stones = |side| -> Vec<Coords> {
+        updated
+            .stones()
+            .into_iter()
+            .filter(|turn| turn.side == side)
+            .map(|turn| turn.coords)
+            .filter(|&coords| matches!(field.get(coords), Some(Cell::Empty)))
+            .collect()
+    };
+    let (mut next, mut other) = match side_to_move(field) {
+        Side::X => (stones(Side::X), stones(Side::O)),
+        Side::O => (stones(Side::O), stones(Side::X)),
+    };
+    next.reverse();
+    other.reverse();
+
+    let mut ordered = vec![];
+    while !next.is_empty() || !other.is_empty() {
+        ordered.extend(next.pop());
+        std::mem::swap(&mut next, &mut other);
+    }
+
+    ordered
+}
+
+/// X moves when both sides have as many stones, O otherwise.
+pub fn side_to_move(field: &Field) -> Side {
+    let count = |side| {
+        (0..field.size.height)
+            .flat_map(|y| (0..field.size.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(field.geti(x, y), Some(Cell::Value(s)) if s == side))
+            .count()
+    };
+
+    match count(Side::X) > count(Side::O) {
+        true => Side::O,
+        false => Side::X,
+    }
+}
+
+impl Display for Opening {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Opening::Swap => write!(f, "swap"),
+            Opening::Swap2 => write!(f, "swap2"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_stones_alternately() {
+        let mut field = Field::from_notation("5x5 4 X 5/5/5/5/5").unwrap().field;
+        let stones = [
+            Coords { x: 2, y: 2 },
+            Coords { x: 1, y: 1 },
+            Coords { x: 3, y: 1 },
+        ];
+
+        let turns = place_stones(&mut field, &stones, 3, RuleSet::STANDARD, 4).unwrap();
+
+        let sides: Vec<Side> = turns.iter().map(|turn| turn.side).collect();
+        assert_eq!(sides, [Side::X, Side::O, Side::X]);
+        assert_eq!(side_to_move(&field), Side::O);
+        assert!(place_stones(&mut field, &stones[..1], 2, RuleSet::STANDARD, 4).is_err());
+    }
+
+    #[test]
+    fn orders_new_stones_alternately() {
+        let field = Field::from_notation("5x5 4 O 5/5/2X2/5/5").unwrap().field;
+        let updated = Field::from_notation("5x5 4 O X4/O4/2X2/5/5").unwrap().field;
+
+        let stones = new_stones(&field, &updated);
+
+        assert_eq!(stones, [Coords { x: 0, y: 1 }, Coords { x: 0, y: 0 }]);
+        assert_eq!(new_stones(&Field::new(field.size), &updated).len(), 3);
+        assert!(new_stones(&updated, &updated).is_empty());
+    }
+
+    #[test]
+    fn opening_may_not_decide_the_game() {
+        let mut field = Field::from_notation("3x3 3 O XO1/X2/3").unwrap().field;
+        let stones = [Coords { x: 2, y: 2 }, Coords { x: 0, y: 2 }];
+
+        assert!(place_stones(&mut field, &stones, 2, RuleSet::STANDARD, 3).is_err());
+    }
+}
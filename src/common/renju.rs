@@ -0,0 +1,229 @@
+//! Forbidden moves of the first player (black, `X`) under Renju rules.
+//!
+//! X may not make an overline, two fours or two open threes with one move,
+//! unless the move also makes exactly `win_condition` in a row. A three is open
+//! when one more move, itself not forbidden, turns it into a straight four:
+//! a four with both ends free.
+
+use std::fmt::Display;
+
+use super::{
+    bot::{Cell, Coords, Field, Side},
+    utils::DIRECTIONS,
+};
+
+/// How deep the forbidden status of the move completing a three is checked.
+static THREE_CHECK_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forbidden {
+    DoubleThree,
+    DoubleFour,
+    Overline,
+}
+
+/// Cells of a board for the detection, `None` outside of it.
+pub trait Stones {
+    fn stone(&self, x: isize, y: isize) -> Option<Cell>;
+}
+
+/// The board with one more X stone.
+struct WithStone<'a> {
+    board: &'a dyn Stones,
+    at: (isize, isize),
+}
+
+type Dir = (isize, isize);
+
+/// Why X may not play at `at`, if it may not. `at` has to be empty.
+pub fn forbidden(
+    board: &dyn Stones,
+    at: (isize, isize),
+    win_condition: usize,
+) -> Option<Forbidden> {
+    forbidden_with_depth(board, at, win_condition, THREE_CHECK_DEPTH)
+}
+
+fn forbidden_with_depth(
+    board: &dyn Stones,
+    at: (isize, isize),
+    win_condition: usize,
+    depth: usize,
+) -> Option<Forbidden> {
+    let board = WithStone { board, at };
+    let dirs = DIRECTIONS.map(|dir| (dir.x as isize, dir.y as isize));
+    let runs = dirs.map(|dir| run_length(&board, at, dir));
+
+    if runs.contains(&win_condition) {
+        return None;
+    }
+    if runs.iter().any(|&run| run > win_condition) {
+        return Some(Forbidden::Overline);
+    }
+
+    let fours: usize = dirs
+        .iter()
+        .map(|&dir| fours(&board, at, dir, win_condition))
+        .sum();
+    if fours >= 2 {
+        return Some(Forbidden::DoubleFour);
+    }
+
+    if depth > 0 {
+        let threes = dirs
+            .iter()
+            .filter(|&&dir| is_open_three(&board, at, dir, win_condition, depth))
+            .count();
+        if threes >= 2 {
+            return Some(Forbidden::DoubleThree);
+        }
+    }
+
+    None
+}
+
+/// Fours through `at` in one direction: a straight four counts once,
+/// a broken line like `X.XXX.X` with two separate fives to make counts twice.
+fn fours(board: &dyn Stones, at: (isize, isize), dir: Dir, win_condition: usize) -> usize {
+    let points = five_points(board, at, dir, win_condition);
+
+    match points[..] {
+        [first, second] if second - first == win_condition as isize => 1,
+        _ => points.len(),
+    }
+}
+
+fn is_straight_four(
+    board: &dyn Stones,
+    at: (isize, isize),
+    dir: Dir,
+    win_condition: usize,
+) -> bool {
+    matches!(
+        five_points(board, at, dir, win_condition)[..],
+        [first, second] if second - first == win_condition as isize
+    )
+}
+
+fn is_open_three(
+    board: &dyn Stones,
+    at: (isize, isize),
+    dir: Dir,
+    win_condition: usize,
+    depth: usize,
+) -> bool {
+    let reach = win_condition as isize - 1;
+
+    (-reach..=reach).filter(|&k| k != 0).any(|k| {
+        let next = shift(at, dir, k);
+        if !matches!(board.stone(next.0, next.1), Some(Cell::Empty)) {
+            return false;
+        }
+
+        let with_next = WithStone { board, at: next };
+        is_straight_four(&with_next, at, dir, win_condition)
+            && forbidden_with_depth(board, next, win_condition, depth - 1).is_none()
+    })
+}
+
+/// Offsets along `dir` of the empty cells where X would make exactly
+/// `win_condition` in a row through `at`.
+fn five_points(
+    board: &dyn Stones,
+    at: (isize, isize),
+    dir: Dir,
+    win_condition: usize,
+) -> Vec<isize> {
+    let reach = win_condition as isize;
+
+    (-reach..=reach)
+        .filter(|&k| {
+            let next = shift(at, dir, k);
+            k != 0
+                && matches!(board.stone(next.0, next.1), Some(Cell::Empty))
+                && run_length(&WithStone { board, at: next }, at, dir) == win_condition
+        })
+        .collect()
+}
+
+fn run_length(board: &dyn Stones, at: (isize, isize), dir: Dir) -> usize {
+    let is_x = |k: isize| {
+        let (x, y) = shift(at, dir, k);
+        matches!(board.stone(x, y), Some(Cell::Value(Side::X)))
+    };
+    let forward = (1..).take_while(|&k| is_x(k)).count();
+    let backward = (1..).take_while(|&k| is_x(-k)).count();
+
+    1 + forward + backward
+}
+
+fn shift(at: (isize, isize), dir: Dir, k: isize) -> (isize, isize) {
+    (at.0 + dir.0 * k, at.1 + dir.1 * k)
+}
+
+impl Stones for WithStone<'_> {
+    fn stone(&self, x: isize, y: isize) -> Option<Cell> {
+        match (x, y) == self.at {
+            true => Some(Cell::Value(Side::X)),
+            false => self.board.stone(x, y),
+        }
+    }
+}
+
+impl Stones for Field {
+    fn stone(&self, x: isize, y: isize) -> Option<Cell> {
+        self.get(Coords { x, y })
+    }
+}
+
+impl Display for Forbidden {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Forbidden::DoubleThree => write!(f, "double three"),
+            Forbidden::DoubleFour => write!(f, "double four"),
+            Forbidden::Overline => write!(f, "overline"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::bot::Coords;
+
+    use super::*;
+
+    fn check(notation: &str, algebraic: &str) -> Option<Forbidden> {
+        let position = Field::from_notation(notation).unwrap();
+        let Coords { x, y } = Coords::from_algebraic(algebraic).unwrap();
+
+        forbidden(&position.field, (x, y), position.win_condition)
+    }
+
+    #[test]
+    fn detects_double_three() {
+        let position = "9x9 5 X 9/9/4X4/4X4/2XX5/9/9/9/9";
+
+        assert_eq!(check(position, "e5"), Some(Forbidden::DoubleThree));
+        assert_eq!(check(position, "f5"), None);
+    }
+
+    #[test]
+    fn blocked_three_is_not_open() {
+        assert_eq!(check("9x9 5 X 9/4O4/4X4/4X4/2XX5/9/9/9/9", "e5"), None);
+    }
+
+    #[test]
+    fn detects_double_four_and_overline() {
+        let double_four = "9x9 5 X 4O4/4X4/4X4/4X4/OXXX5/9/9/9/9";
+        assert_eq!(check(double_four, "e5"), Some(Forbidden::DoubleFour));
+        assert_eq!(
+            check("9x9 5 X 9/9/9/9/XXX1XX3/9/9/9/9", "d5"),
+            Some(Forbidden::Overline)
+        );
+    }
+
+    #[test]
+    fn five_beats_forbidden_shapes() {
+        assert_eq!(check("9x9 5 X 9/9/4X4/4X4/XXXX5/9/9/9/9", "e5"), None);
+    }
+}
@@ -0,0 +1,585 @@
+//! Rules of the game variants.
+//!
+//! A `RuleSet` is written as a comma-separated list of the variants it enables,
+//! e.g. `misere` or `gravity,misere`, or `standard` when none are. `infinite`
+//! plays on a field without edges, where the field size doesn't matter. Renju
+//! restrictions are in `common::renju`, opening rules in `common::opening` and
+//! ultimate tic-tac-toe in `common::ultimate`.
+
+use std::{collections::BTreeSet, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bot::{Cell, Coords, Field, Side, Size, Topology, Turn},
+    error::CoreError,
+    opening::Opening,
+    renju::{self, Forbidden},
+    ultimate::{self, UltimatePosition},
+    utils::{Direction, DIRECTIONS},
+};
+
+/// How far from the stones moves are looked for on infinite fields.
+pub static NEIGHBORHOOD: isize = 2;
+/// Stones after which a game on an infinite field is a draw, as much as a 20x20 field holds.
+pub static INFINITE_MOVE_LIMIT: usize = 400;
+
+#[derive(Clone, Copy)]
+pub struct GameSettings {
+    pub field_size: Size,
+    pub win_condition: usize,
+    pub rules: RuleSet,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RuleSet {
+    /// Making `win_condition` in a row loses instead of winning.
+    pub misere: bool,
+    /// A move names only a column, the stone falls to its lowest empty cell (`y = 0`).
+    pub gravity: bool,
+    /// `torus` makes lines wrap around the edges of the field, `infinite` takes them away.
+    pub topology: Topology,
+    /// Only runs of exactly `win_condition` stones win, longer ones (overlines) don't.
+    pub exact: bool,
+    /// X wins only with exactly `win_condition` in a row and may not make
+    /// double threes, double fours and overlines.
+    pub renju: bool,
+    /// Swap or Swap2: the sides are chosen after the opening stones are placed.
+    pub opening: Option<Opening>,
+    /// Ultimate tic-tac-toe on a 9x9 field, `win_condition` doesn't matter.
+    pub ultimate: bool,
+}
+
+impl RuleSet {
+    pub const STANDARD: RuleSet = RuleSet {
+        misere: false,
+        gravity: false,
+        topology: Topology::Bounded,
+        exact: false,
+        renju: false,
+        opening: None,
+        ultimate: false,
+    };
+
+    pub fn parse(value: &str) -> Result<RuleSet, CoreError> {
+        let mut rules = RuleSet::STANDARD;
+        for name in value.split(',').map(str::trim) {
+            let name = name.to_ascii_lowercase();
+            let topology = match name.as_str() {
+                "torus" => Some(Topology::Torus),
+                "infinite" => Some(Topology::Infinite),
+                _ => None,
+            };
+            if let Some(topology) = topology {
+                if rules.topology != Topology::Bounded && rules.topology != topology {
+                    return Err(CoreError::of_incompatible_rules("torus", "infinite"));
+                }
+            }
+
+            match name.as_str() {
+                "standard" => {}
+                "misere" => rules.misere = true,
+                "gravity" => rules.gravity = true,
+                "torus" => rules.topology = Topology::Torus,
+                "infinite" => rules.topology = Topology::Infinite,
+                "exact" => rules.exact = true,
+                "renju" => rules.renju = true,
+                "swap" => rules.opening = Some(Opening::Swap),
+                "swap2" => rules.opening = Some(Opening::Swap2),
+                "ultimate" => rules.ultimate = true,
+                _ => return Err(CoreError::of_unknown_rules(value)),
+            }
+        }
+        // forbidden shapes don't wrap around the edges
+        if rules.renju && rules.topology == Topology::Torus {
+            return Err(CoreError::of_incompatible_rules("renju", "torus"));
+        }
+        // stones have nowhere to fall to
+        if rules.gravity && rules.topology == Topology::Infinite {
+            return Err(CoreError::of_incompatible_rules("gravity", "infinite"));
+        }
+        // boards restrict the moves and make the lines, only misere goes with them
+        if rules.ultimate {
+            let other = [
+                (rules.gravity, "gravity"),
+                (rules.topology == Topology::Torus, "torus"),
+                (rules.topology == Topology::Infinite, "infinite"),
+                (rules.exact, "exact"),
+                (rules.renju, "renju"),
+                (rules.opening.is_some(), "opening"),
+            ];
+            if let Some((_, name)) = other.into_iter().find(|(enabled, _)| *enabled) {
+                return Err(CoreError::of_incompatible_rules("ultimate", name));
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Whether a game can be played on a field of `size`.
+    pub fn check_field_size(&self, size: Size) -> Result<(), CoreError> {
+        let field_size = ultimate::FIELD_SIZE;
+        match self.ultimate && (size.width, size.height) != (field_size.width, field_size.height) {
+            true => Err(CoreError::of_ultimate_field(size)),
+            false => Ok(()),
+        }
+    }
+
+    /// An empty field for a game by these rules.
+    pub fn new_field(&self, size: Size) -> Field {
+        match self.topology {
+            Topology::Infinite => Field::infinite(),
+            _ => Field::new(size),
+        }
+    }
+
+    /// Cells `side` can make a move to. On infinite fields, where every empty cell
+    /// is, only those within `NEIGHBORHOOD` of the stones, or the origin of an empty field.
+    pub fn legal_moves(&self, field: &Field, side: Side, win_condition: usize) -> Vec<Coords> {
+        if self.ultimate {
+            return UltimatePosition::from_field(field, side)
+                .map(|position| position.legal_moves())
+                .unwrap_or_default();
+        }
+
+        let mut moves = vec![];
+        if self.gravity {
+            moves.extend((0..field.size.width as isize).filter_map(|x| drop_target(field, x)));
+        } else if self.topology == Topology::Infinite {
+            moves.extend(near_stones(field));
+        } else {
+            for y in 0..field.size.height as isize {
+                for x in 0..field.size.width as isize {
+                    if let Some(Cell::Empty) = field.get(Coords { x, y }) {
+                        moves.push(Coords { x, y });
+                    }
+                }
+            }
+        }
+        moves.retain(|&coords| self.forbidden(field, coords, side, win_condition).is_none());
+
+        moves
+    }
+
+    pub fn is_legal(&self, field: &Field, turn: &Turn, win_condition: usize) -> bool {
+        if self.ultimate {
+            return UltimatePosition::from_field(field, turn.side)
+                .is_ok_and(|position| position.check(turn.coords).is_ok());
+        }
+        let free = match self.gravity {
+            true => drop_target(field, turn.coords.x) == Some(turn.coords),
+            false => matches!(field.get(turn.coords), Some(Cell::Empty)),
+        };
+
+        free && self
+            .forbidden(field, turn.coords, turn.side, win_condition)
+            .is_none()
+    }
+
+    /// Why `side` may not move to the empty cell `coords`, if it may not.
+    pub fn forbidden(
+        &self,
+        field: &Field,
+        coords: Coords,
+        side: Side,
+        win_condition: usize,
+    ) -> Option<Forbidden> {
+        match self.renju && side == Side::X {
+            true => renju::forbidden(field, (coords.x, coords.y), win_condition),
+            false => None,
+        }
+    }
+
+    /// Makes `turn` on `field` and returns it as made: with gravity only the column
+    /// of the turn counts and the stone lands on the lowest empty cell of it.
+    pub fn place(
+        &self,
+        field: &mut Field,
+        turn: &Turn,
+        win_condition: usize,
+    ) -> Result<Turn, CoreError> {
+        let turn = match self.gravity {
+            true => Turn {
+                coords: drop_target(field, turn.coords.x)
+                    .ok_or_else(|| CoreError::of_full_column(turn.coords.x))?,
+                side: turn.side,
+            },
+            false => *turn,
+        };
+        if self.ultimate {
+            UltimatePosition::from_field(field, turn.side)?.check(turn.coords)?;
+        }
+        if matches!(field.get(turn.coords), Some(Cell::Empty)) {
+            if let Some(forbidden) = self.forbidden(field, turn.coords, turn.side, win_condition) {
+                return Err(CoreError::of_forbidden(turn.coords, forbidden));
+            }
+        }
+        field.add_turn(&turn)?;
+
+        Ok(turn)
+    }
+
+    /// Whether overlines don't win for `side`.
+    pub fn is_exact_for(&self, side: Side) -> bool {
+        self.exact || self.renju && side == Side::X
+    }
+
+    /// The side that won by `turn`, if it decided the game.
+    pub fn winner_after(&self, field: &Field, turn: &Turn, win_condition: usize) -> Option<Side> {
+        match self.is_win(field, turn.side, win_condition) {
+            true => Some(self.line_owner_result(turn.side)),
+            false => None,
+        }
+    }
+
+    /// The winner of a finished game, found from its final position.
+    pub fn winner(&self, field: &Field, win_condition: usize) -> Option<Side> {
+        [Side::X, Side::O]
+            .into_iter()
+            .find(|&side| self.is_win(field, side, win_condition))
+            .map(|side| self.line_owner_result(side))
+    }
+
+    /// Whether `side` has a winning run: at least `win_condition` in a row,
+    /// or exactly that many when overlines don't count. With ultimate rules,
+    /// three boards in a row.
+    pub fn is_win(&self, field: &Field, side: Side, win_condition: usize) -> bool {
+        match self.ultimate {
+            true => UltimatePosition::from_field(field, side)
+                .is_ok_and(|position| position.winner() == Some(side)),
+            false => self.winning_line(field, side, win_condition).is_some(),
+        }
+    }
+
+    /// Whether no turns are left: the field is full or, with ultimate rules,
+    /// every board is decided. Infinite fields are never full, their games end
+    /// after `INFINITE_MOVE_LIMIT` stones.
+    pub fn is_exhausted(&self, field: &Field) -> bool {
+        match self.ultimate {
+            true => UltimatePosition::from_field(field, Side::X)
+                .map_or(true, |position| position.is_exhausted()),
+            false if field.is_infinite() => field.stones().len() >= INFINITE_MOVE_LIMIT,
+            false => field.is_full(),
+        }
+    }
+
+    pub fn winning_line(
+        &self,
+        field: &Field,
+        side: Side,
+        win_condition: usize,
+    ) -> Option<Vec<Coords>> {
+        let stones = field.stones().into_iter().filter(|turn| turn.side == side);
+        for start in stones.map(|turn| turn.coords) {
+            for dir in DIRECTIONS {
+                let in_a_row = self.count_in_a_row(field, side, start, dir);
+                // runs are counted from their first stone only, save for a run
+                // all around the torus that has none
+                let previous = self.topology.neighbor(field.size, start, dir.invert(), 1);
+                let is_ring = Some(in_a_row) == self.topology.period(field.size, dir);
+                if is_side(field, previous, side) && !is_ring {
+                    continue;
+                }
+                let wins = match self.is_exact_for(side) {
+                    true => in_a_row == win_condition,
+                    false => in_a_row >= win_condition,
+                };
+                if wins {
+                    return (0..in_a_row as isize)
+                        .map(|k| self.topology.neighbor(field.size, start, dir, k))
+                        .collect();
+                }
+            }
+        }
+
+        None
+    }
+
+    fn count_in_a_row(&self, field: &Field, side: Side, start: Coords, dir: Direction) -> usize {
+        let limit = self.topology.period(field.size, dir).unwrap_or(usize::MAX);
+        let mut in_a_row = 0;
+
+        while in_a_row < limit {
+            let next = self
+                .topology
+                .neighbor(field.size, start, dir, in_a_row as isize);
+            if !is_side(field, next, side) {
+                break;
+            }
+            in_a_row += 1;
+        }
+
+        in_a_row
+    }
+
+    fn line_owner_result(&self, side: Side) -> Side {
+        match self.misere {
+            true => side.opposite(),
+            false => side,
+        }
+    }
+}
+
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let opening = self.opening.map(|opening| opening.to_string());
+        let names: Vec<&str> = [
+            (self.gravity, "gravity"),
+            (self.topology == Topology::Torus, "torus"),
+            (self.topology == Topology::Infinite, "infinite"),
+            (self.exact, "exact"),
+            (self.renju, "renju"),
+            (opening.is_some(), opening.as_deref().unwrap_or_default()),
+            (self.ultimate, "ultimate"),
+            (self.misere, "misere"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+
+        match names.is_empty() {
+            true => write!(f, "standard"),
+            false => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+impl TryFrom<String> for RuleSet {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        RuleSet::parse(&value).map_err(|e| e.message)
+    }
+}
+
+impl From<RuleSet> for String {
+    fn from(rules: RuleSet) -> Self {
+        rules.to_string()
+    }
+}
+
+/// The lowest empty cell of column `x`.
+pub fn drop_target(field: &Field, x: isize) -> Option<Coords> {
+    (0..field.size.height as isize)
+        .map(|y| Coords { x, y })
+        .find(|&coords| matches!(field.get(coords), Some(Cell::Empty)))
+}
+
+/// Empty cells within `NEIGHBORHOOD` of the stones, row by row.
+fn near_stones(field: &Field) -> Vec<Coords> {
+    let stones = field.stones();
+    if stones.is_empty() {
+        return vec![Coords { x: 0, y: 0 }];
+    }
+
+    let mut cells = BTreeSet::new();
+    for turn in stones {
+        for dy in -NEIGHBORHOOD..=NEIGHBORHOOD {
+            for dx in -NEIGHBORHOOD..=NEIGHBORHOOD {
+                let x = turn.coords.x.checked_add(dx);
+                let y = turn.coords.y.checked_add(dy);
+                if let (Some(x), Some(y)) = (x, y) {
+                    if let Some(Cell::Empty) = field.get(Coords { x, y }) {
+                        cells.insert((y, x));
+                    }
+                }
+            }
+        }
+    }
+
+    cells.into_iter().map(|(y, x)| Coords { x, y }).collect()
+}
+
+fn is_side(field: &Field, coords: Option<Coords>, side: Side) -> bool {
+    matches!(coords.and_then(|c| field.get(c)), Some(Cell::Value(s)) if s == side)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rule_sets() {
+        assert_eq!(RuleSet::parse("standard").unwrap(), RuleSet::STANDARD);
+        assert!(RuleSet::parse("Misere").unwrap().misere);
+        assert!(RuleSet::parse("misere,chess").is_err());
+        assert_eq!(
+            RuleSet::parse("misere, gravity").unwrap().to_string(),
+            "gravity,misere"
+        );
+        assert_eq!(
+            RuleSet::parse("swap2,renju").unwrap().to_string(),
+            "renju,swap2"
+        );
+        assert_eq!(
+            RuleSet::parse("misere,ultimate").unwrap().to_string(),
+            "ultimate,misere"
+        );
+        assert!(RuleSet::parse("ultimate,gravity").is_err());
+    }
+
+    #[test]
+    fn overline_wins_only_without_exact() {
+        let field = Field::from_notation("7x1 5 O XXXXXX1").unwrap().field;
+        let exact = RuleSet::parse("exact").unwrap();
+
+        assert_eq!(
+            RuleSet::STANDARD
+                .winning_line(&field, Side::X, 5)
+                .unwrap()
+                .len(),
+            6
+        );
+        assert!(!exact.is_win(&field, Side::X, 5));
+        assert!(exact.is_win(&field, Side::X, 6));
+    }
+
+    #[test]
+    fn lines_wrap_around_torus() {
+        let field = Field::from_notation("7x2 4 O XX3XX/7").unwrap().field;
+        let torus = RuleSet::parse("torus").unwrap();
+
+        assert!(!RuleSet::STANDARD.is_win(&field, Side::X, 4));
+        let line = torus.winning_line(&field, Side::X, 4).unwrap();
+        let columns: Vec<isize> = line.iter().map(|coords| coords.x).collect();
+        assert_eq!(columns, [5, 6, 0, 1]);
+
+        let ring = Field::from_notation("4x2 4 O XXXX/4").unwrap().field;
+        let exact_torus = RuleSet::parse("torus,exact").unwrap();
+        assert!(exact_torus.is_win(&ring, Side::X, 4));
+        assert!(!exact_torus.is_win(&ring, Side::X, 3));
+        assert!(RuleSet::parse("torus,renju").is_err());
+    }
+
+    #[test]
+    fn stones_fall_with_gravity() {
+        let mut field = Field::from_notation("4x3 3 X XO2/X3/4").unwrap().field;
+        let gravity = RuleSet::parse("gravity").unwrap();
+        let turn = Turn {
+            coords: Coords { x: 0, y: 2 },
+            side: Side::X,
+        };
+
+        let placed = gravity.place(&mut field, &turn, 3).unwrap();
+
+        assert_eq!(placed.coords, Coords { x: 0, y: 2 });
+        assert!(gravity.place(&mut field, &turn, 3).is_err());
+        let legal = gravity.legal_moves(&field, Side::O, 3);
+        assert_eq!(
+            legal,
+            [
+                Coords { x: 1, y: 1 },
+                Coords { x: 2, y: 0 },
+                Coords { x: 3, y: 0 }
+            ]
+        );
+        let floating = Turn {
+            coords: Coords { x: 2, y: 1 },
+            side: Side::O,
+        };
+        assert!(!gravity.is_legal(&field, &floating, 3));
+    }
+
+    #[test]
+    fn line_loses_in_misere() {
+        let position = Field::from_notation("3x3 3 O XXX/OO1/3").unwrap();
+        let turn = Turn {
+            coords: Coords { x: 2, y: 0 },
+            side: Side::X,
+        };
+        let misere = RuleSet::parse("misere").unwrap();
+
+        assert_eq!(
+            RuleSet::STANDARD.winner_after(&position.field, &turn, 3),
+            Some(Side::X)
+        );
+        assert_eq!(
+            misere.winner_after(&position.field, &turn, 3),
+            Some(Side::O)
+        );
+        assert_eq!(misere.winner(&position.field, 3), Some(Side::O));
+    }
+
+    #[test]
+    fn infinite_field_grows_around_stones() {
+        let infinite = RuleSet::parse("infinite").unwrap();
+        let mut field = infinite.new_field(Size {
+            width: 15,
+            height: 15,
+        });
+        assert_eq!(
+            infinite.legal_moves(&field, Side::X, 5),
+            [Coords { x: 0, y: 0 }]
+        );
+
+        for x in (-3..=0).rev() {
+            let turn = Turn {
+                coords: Coords { x, y: 0 },
+                side: Side::X,
+            };
+            infinite.place(&mut field, &turn, 5).unwrap();
+        }
+        // an 8x5 neighborhood around the row of stones
+        assert_eq!(infinite.legal_moves(&field, Side::O, 5).len(), 8 * 5 - 4);
+        assert!(!infinite.is_win(&field, Side::X, 5));
+        let turn = Turn {
+            coords: Coords { x: -4, y: 0 },
+            side: Side::X,
+        };
+        infinite.place(&mut field, &turn, 5).unwrap();
+        assert!(infinite.is_win(&field, Side::X, 5));
+        assert!(RuleSet::parse("torus,infinite").is_err());
+    }
+
+    #[test]
+    fn infinite_game_ends_at_move_limit() {
+        let infinite = RuleSet::parse("infinite").unwrap();
+        let mut field = Field::infinite();
+        for index in 0..INFINITE_MOVE_LIMIT as isize {
+            assert!(!infinite.is_exhausted(&field));
+            let turn = Turn {
+                coords: Coords {
+                    x: index % 20,
+                    y: index / 20,
+                },
+                side: Side::X,
+            };
+            field.add_turn(&turn).unwrap();
+        }
+
+        assert!(infinite.is_exhausted(&field));
+    }
+
+    #[test]
+    fn allows_distant_moves_on_infinite_field() {
+        let infinite = RuleSet::parse("infinite").unwrap();
+        let mut field = infinite.new_field(Size {
+            width: 0,
+            height: 0,
+        });
+        let turn = |x, y, side| Turn {
+            coords: Coords { x, y },
+            side,
+        };
+
+        infinite.place(&mut field, &turn(0, 0, Side::X), 5).unwrap();
+        for (x, y) in [(1_000_000_000, 0), (isize::MAX, isize::MIN), (3, -2)] {
+            let distant = turn(x, y, Side::O);
+            assert!(infinite.is_legal(&field, &distant, 5));
+            infinite.place(&mut field, &distant, 5).unwrap();
+        }
+
+        let moves = infinite.legal_moves(&field, Side::X, 5);
+        assert!(moves.contains(&Coords { x: 2, y: 0 }));
+        assert!(moves.contains(&Coords {
+            x: isize::MAX,
+            y: isize::MIN + 2
+        }));
+        assert!(!moves.contains(&Coords { x: 6, y: 0 }));
+        let (window, origin) = field.window(5);
+        assert_eq!((window.size.width, window.size.height), (14, 13));
+        assert_eq!(origin, Coords { x: -5, y: -7 });
+        assert!(RuleSet::parse("gravity,infinite").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{error::GameProcessError, game_process::GameResult};
+
+pub static EXIT_OK: i32 = 0;
+pub static EXIT_ERRORS: i32 = 1;
+pub static EXIT_INTERRUPTED: i32 = 130;
+
+static SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// Shared flag telling running players to stop.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+}
+
+/// What a player does with the game in progress when shutdown is requested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ShutdownPolicy {
+    /// Play the current game to the end.
+    #[default]
+    Finish,
+    /// Stop playing immediately. The server has no way to resign,
+    /// so the game is abandoned and counted as a defeat.
+    Resign,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub errors: usize,
+    pub interrupted: bool,
+}
+
+impl Shutdown {
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Sleeps for `duration` or until shutdown is requested.
+    pub fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.is_requested() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(SLEEP_STEP));
+        }
+    }
+}
+
+impl ShutdownPolicy {
+    pub fn parse(value: &str) -> Option<ShutdownPolicy> {
+        match value.to_ascii_lowercase().as_str() {
+            "finish" => Some(ShutdownPolicy::Finish),
+            "resign" => Some(ShutdownPolicy::Resign),
+            _ => None,
+        }
+    }
+}
+
+impl SessionSummary {
+    pub fn add(&mut self, result: &Result<GameResult, GameProcessError>) {
+        match result {
+            Ok(GameResult::Win) => self.wins += 1,
+            Ok(GameResult::Draw) => self.draws += 1,
+            Ok(GameResult::Defeat) => self.losses += 1,
+            Err(GameProcessError::Interrupted) => self.interrupted = true,
+            Err(_) => self.errors += 1,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 1 if any game failed, 130 if the session was interrupted, 0 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        if self.errors > 0 {
+            EXIT_ERRORS
+        } else if self.interrupted {
+            EXIT_INTERRUPTED
+        } else {
+            EXIT_OK
+        }
+    }
+}
+
+impl AddAssign for SessionSummary {
+    fn add_assign(&mut self, other: SessionSummary) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.errors += other.errors;
+        self.interrupted |= other.interrupted;
+    }
+}
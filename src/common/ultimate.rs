@@ -0,0 +1,271 @@
+//! Ultimate tic-tac-toe: the 9x9 field is a 3x3 grid of 3x3 boards. A move to
+//! a cell sends the opponent to the board in the same place of the grid, unless
+//! that board is decided, then any open board will do. Three boards won in a
+//! row win the game.
+//!
+//! Boards are numbered like the cells of a board, `x + 3 * y` from the bottom
+//! left, and named like the cells of a 3x3 field, e.g. `b2` is the middle one.
+
+use super::{
+    bot::{Cell, Coords, Field, Side, Size},
+    error::CoreError,
+};
+
+pub static BOARD_SIZE: usize = 3;
+pub static FIELD_SIZE: Size = Size {
+    width: 9,
+    height: 9,
+};
+
+static BOARDS: usize = 9;
+static CELLS: usize = 81;
+
+/// Lines of a 3x3 board, by cell numbers.
+static LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BoardState {
+    Open,
+    Won(Side),
+    /// Filled up without a line.
+    Full,
+}
+
+#[derive(Clone)]
+pub struct UltimatePosition {
+    /// Indexed by `y * 9 + x` of the field.
+    cells: [Option<Side>; CELLS],
+    boards: [BoardState; BOARDS],
+    /// The board the last move sent to, `None` when any open board will do.
+    next_board: Option<usize>,
+    side: Side,
+    winner: Option<Side>,
+}
+
+impl UltimatePosition {
+    /// The position on `field` with `side` to move. The board to play on comes
+    /// from the last move of the field.
+    pub fn from_field(field: &Field, side: Side) -> Result<Self, CoreError> {
+        if field.size.width != FIELD_SIZE.width || field.size.height != FIELD_SIZE.height {
+            return Err(CoreError::of_ultimate_field(field.size));
+        }
+
+        let mut cells = [None; CELLS];
+        let width = FIELD_SIZE.width;
+        for (index, cell) in cells.iter_mut().enumerate() {
+            if let Some(Cell::Value(side)) = field.geti(index % width, index / width) {
+                *cell = Some(side);
+            }
+        }
+        let mut position = UltimatePosition {
+            cells,
+            boards: [BoardState::Open; BOARDS],
+            next_board: None,
+            side,
+            winner: None,
+        };
+        for board in 0..BOARDS {
+            position.boards[board] = position.board_state(board);
+        }
+        position.winner = position.meta_winner();
+        position.next_board = field
+            .last_move()
+            .map(sent_to)
+            .filter(|&board| position.is_open(board));
+
+        Ok(position)
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        self.winner
+    }
+
+    /// Whether no board is left to play on.
+    pub fn is_exhausted(&self) -> bool {
+        !(0..BOARDS).any(|board| self.is_open(board))
+    }
+
+    pub fn legal_moves(&self) -> Vec<Coords> {
+        if self.winner.is_some() {
+            return vec![];
+        }
+
+        let boards = match self.next_board {
+            Some(board) => board..board + 1,
+            None => 0..BOARDS,
+        };
+        boards
+            .filter(|&board| self.is_open(board))
+            .flat_map(|board| (0..BOARDS).map(move |cell| cell_coords(board, cell)))
+            .filter(|coords| self.cells[index(*coords)].is_none())
+            .collect()
+    }
+
+    /// Why the side to move may not move to `coords`, if it may not.
+    pub fn check(&self, coords: Coords) -> Result<(), CoreError> {
+        let (width, height) = (FIELD_SIZE.width as isize, FIELD_SIZE.height as isize);
+        if !(0..width).contains(&coords.x) || !(0..height).contains(&coords.y) {
+            return Err(CoreError::of_wrong_coords(coords, FIELD_SIZE));
+        }
+        if let Some(current) = self.cells[index(coords)] {
+            return Err(CoreError::of_filled_cell(coords, current));
+        }
+
+        let board = board_of(coords);
+        match self.next_board {
+            _ if !self.is_open(board) => Err(CoreError::of_decided_board(coords)),
+            Some(next_board) if next_board != board => {
+                Err(CoreError::of_wrong_board(coords, board_name(next_board)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Moves to `coords` for the side to move and passes the turn.
+    pub fn play(&mut self, coords: Coords) -> Result<(), CoreError> {
+        self.check(coords)?;
+
+        let board = board_of(coords);
+        self.cells[index(coords)] = Some(self.side);
+        self.boards[board] = self.board_state(board);
+        if let BoardState::Won(_) = self.boards[board] {
+            self.winner = self.meta_winner();
+        }
+        self.next_board = Some(sent_to(coords)).filter(|&board| self.is_open(board));
+        self.side = self.side.opposite();
+
+        Ok(())
+    }
+
+    fn is_open(&self, board: usize) -> bool {
+        self.boards[board] == BoardState::Open
+    }
+
+    fn board_state(&self, board: usize) -> BoardState {
+        let cell = |cell| self.cells[index(cell_coords(board, cell))];
+        let line_owner = LINES.iter().find_map(|line| match line.map(cell) {
+            [Some(side), second, third] if second == Some(side) && third == Some(side) => {
+                Some(side)
+            }
+            _ => None,
+        });
+
+        match line_owner {
+            Some(side) => BoardState::Won(side),
+            None if (0..BOARDS).all(|index| cell(index).is_some()) => BoardState::Full,
+            None => BoardState::Open,
+        }
+    }
+
+    fn meta_winner(&self) -> Option<Side> {
+        LINES
+            .iter()
+            .find_map(|line| match line.map(|board| self.boards[board]) {
+                [BoardState::Won(side), second, third]
+                    if second == BoardState::Won(side) && third == BoardState::Won(side) =>
+                {
+                    Some(side)
+                }
+                _ => None,
+            })
+    }
+}
+
+/// The board `coords` is on.
+pub fn board_of(coords: Coords) -> usize {
+    coords.y as usize / BOARD_SIZE * BOARD_SIZE + coords.x as usize / BOARD_SIZE
+}
+
+/// The board a move to `coords` sends the opponent to.
+pub fn sent_to(coords: Coords) -> usize {
+    coords.y as usize % BOARD_SIZE * BOARD_SIZE + coords.x as usize % BOARD_SIZE
+}
+
+fn board_name(board: usize) -> Coords {
+    Coords {
+        x: (board % BOARD_SIZE) as isize,
+        y: (board / BOARD_SIZE) as isize,
+    }
+}
+
+fn cell_coords(board: usize, cell: usize) -> Coords {
+    Coords {
+        x: (board % BOARD_SIZE * BOARD_SIZE + cell % BOARD_SIZE) as isize,
+        y: (board / BOARD_SIZE * BOARD_SIZE + cell / BOARD_SIZE) as isize,
+    }
+}
+
+fn index(coords: Coords) -> usize {
+    coords.y as usize * FIELD_SIZE.width + coords.x as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(name: &str) -> Coords {
+        Coords::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn sends_opponent_to_matching_board() {
+        let mut position = UltimatePosition::from_field(&Field::new(FIELD_SIZE), Side::X).unwrap();
+        assert_eq!(position.legal_moves().len(), 81);
+
+        position.play(coords("e5")).unwrap();
+
+        let moves = position.legal_moves();
+        assert_eq!(moves.len(), 8);
+        assert!(moves.iter().all(|&coords| board_of(coords) == 4));
+        assert!(position.play(coords("a1")).is_err());
+        position.play(coords("d4")).unwrap();
+        assert!(position
+            .legal_moves()
+            .iter()
+            .all(|&coords| board_of(coords) == 0));
+    }
+
+    #[test]
+    fn decided_board_frees_the_choice() {
+        // d1 sends to the board of a1, which X has won
+        let position = "9x9 3 X XXXO5/9/9/9/9/9/9/9/9 d1";
+        let field = Field::from_notation(position).unwrap().field;
+
+        let position = UltimatePosition::from_field(&field, Side::X).unwrap();
+
+        assert_eq!(position.legal_moves().len(), 71);
+        assert!(position.check(coords("b2")).is_err());
+    }
+
+    #[test]
+    fn three_boards_in_a_row_win() {
+        let position = "9x9 3 O XXX6/O8/O8/3XXX3/9/9/6XXX/9/9";
+        let field = Field::from_notation(position).unwrap().field;
+
+        let position = UltimatePosition::from_field(&field, Side::O).unwrap();
+
+        assert_eq!(position.winner(), Some(Side::X));
+        assert!(position.legal_moves().is_empty());
+        assert!(UltimatePosition::from_field(
+            &Field::new(Size {
+                width: 3,
+                height: 3
+            }),
+            Side::X
+        )
+        .is_err());
+    }
+}
@@ -15,10 +15,36 @@ impl Direction {
     }
 }
 
-pub static DIRECTIONS: [Direction; 3] = [
+pub static DIRECTIONS: [Direction; 4] = [
     Direction { x: 1, y: 0 },
     Direction { x: 0, y: 1 },
     Direction { x: 1, y: 1 },
+    Direction { x: 1, y: -1 },
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Direction3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// One of each pair of opposite directions in a cube: 3 along the axes,
+/// 6 face diagonals and 4 space diagonals.
+pub static DIRECTIONS_3D: [Direction3; 13] = [
+    Direction3 { x: 1, y: 0, z: 0 },
+    Direction3 { x: 0, y: 1, z: 0 },
+    Direction3 { x: 0, y: 0, z: 1 },
+    Direction3 { x: 1, y: 1, z: 0 },
+    Direction3 { x: 1, y: -1, z: 0 },
+    Direction3 { x: 1, y: 0, z: 1 },
+    Direction3 { x: 1, y: 0, z: -1 },
+    Direction3 { x: 0, y: 1, z: 1 },
+    Direction3 { x: 0, y: 1, z: -1 },
+    Direction3 { x: 1, y: 1, z: 1 },
+    Direction3 { x: 1, y: 1, z: -1 },
+    Direction3 { x: 1, y: -1, z: 1 },
+    Direction3 { x: 1, y: -1, z: -1 },
 ];
 
 impl Mul<i32> for Direction {
@@ -0,0 +1,212 @@
+//! Fleet configuration file.
+//!
+//! ```json
+//! {
+//!   "records_dir": "records",
+//!   "snapshots_dir": "snapshots",
+//!   "report_interval_secs": 30,
+//!   "players": [
+//!     { "name": "search-v2", "bot": "search 500", "server": "http://localhost:8080",
+//!       "games": 0, "restart": "on_error", "max_restarts": 5, "restart_delay_ms": 1000,
+//!       "request_timeout_secs": 60 },
+//!     { "name": "random", "bot": "random", "games": 10 }
+//!   ]
+//! }
+//! ```
+//!
+//! Only `name` is required for a player. `games: 0` plays until shutdown,
+//! `max_restarts` left out means restarting without a limit.
+//!
+//! Snapshots of a player's games are kept in `<snapshots_dir>/<name>`. Ones
+//! left in `snapshots_dir` itself by older versions are only resumed once moved
+//! into the directory of their player.
+
+use std::{collections::HashSet, fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::{bots, hooks::api_hooks::DEFAULT_SERVER};
+
+static DEFAULT_BOT: &str = "random";
+static DEFAULT_RECORDS_DIR: &str = "records";
+static DEFAULT_SNAPSHOTS_DIR: &str = "snapshots";
+static DEFAULT_REPORT_INTERVAL_SECS: u64 = 30;
+static DEFAULT_RESTART_DELAY_MS: u64 = 1000;
+static DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 3600;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FleetConfig {
+    pub players: Vec<PlayerConfig>,
+    #[serde(default = "default_records_dir")]
+    pub records_dir: String,
+    #[serde(default = "default_snapshots_dir")]
+    pub snapshots_dir: String,
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayerConfig {
+    pub name: String,
+    #[serde(default = "default_bot")]
+    pub bot: String,
+    #[serde(default = "default_server")]
+    pub server: String,
+    #[serde(default = "default_games")]
+    pub games: usize,
+    #[serde(default = "default_pondering")]
+    pub pondering: bool,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default)]
+    pub max_restarts: Option<usize>,
+    #[serde(default = "default_restart_delay_ms")]
+    pub restart_delay_ms: u64,
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+/// What a player does after a game failed with an error.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Stop the player.
+    #[default]
+    Never,
+    /// Start a new game after `restart_delay_ms`, growing with every error in a row.
+    OnError,
+}
+
+impl FleetConfig {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let config: FleetConfig = serde_json::from_str(&fs::read_to_string(path)?)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// A fleet of `count` players with the same bot, as in the `play` mode.
+    pub fn of_bot(bot: &str, count: usize, games: usize) -> Self {
+        FleetConfig {
+            players: (1..=count)
+                .map(|i| PlayerConfig {
+                    bot: bot.to_owned(),
+                    games,
+                    ..PlayerConfig::named(&format!("player-{i}"))
+                })
+                .collect(),
+            records_dir: default_records_dir(),
+            snapshots_dir: default_snapshots_dir(),
+            report_interval_secs: default_report_interval_secs(),
+        }
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if self.players.is_empty() {
+            return Err(invalid("fleet has no players".to_owned()));
+        }
+        let mut names = HashSet::new();
+        for player in &self.players {
+            if !names.insert(&player.name) {
+                return Err(invalid(format!("duplicate player name {:?}", player.name)));
+            }
+            if let Err(e) = bots::from_spec(&player.bot) {
+                return Err(invalid(format!("player {:?}: {}", player.name, e.message)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PlayerConfig {
+    pub fn named(name: &str) -> Self {
+        PlayerConfig {
+            name: name.to_owned(),
+            bot: default_bot(),
+            server: default_server(),
+            games: default_games(),
+            pondering: default_pondering(),
+            restart: RestartPolicy::default(),
+            max_restarts: None,
+            restart_delay_ms: default_restart_delay_ms(),
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+fn default_records_dir() -> String {
+    DEFAULT_RECORDS_DIR.to_owned()
+}
+
+fn default_snapshots_dir() -> String {
+    DEFAULT_SNAPSHOTS_DIR.to_owned()
+}
+
+fn default_report_interval_secs() -> u64 {
+    DEFAULT_REPORT_INTERVAL_SECS
+}
+
+fn default_bot() -> String {
+    DEFAULT_BOT.to_owned()
+}
+
+fn default_server() -> String {
+    DEFAULT_SERVER.to_owned()
+}
+
+fn default_games() -> usize {
+    1
+}
+
+fn default_pondering() -> bool {
+    true
+}
+
+fn default_restart_delay_ms() -> u64 {
+    DEFAULT_RESTART_DELAY_MS
+}
+
+fn default_request_timeout_secs() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_player_defaults() {
+        let config: FleetConfig = serde_json::from_str(
+            r#"{ "players": [{ "name": "a" }, { "name": "b", "bot": "search 100",
+                 "games": 0, "restart": "on_error", "max_restarts": 3 }] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.records_dir, "records");
+        let [a, b] = &config.players[..] else {
+            panic!("expected two players");
+        };
+        assert_eq!(
+            (a.bot.as_str(), a.games, a.restart),
+            ("random", 1, RestartPolicy::Never)
+        );
+        assert_eq!(a.server, DEFAULT_SERVER);
+        assert_eq!(a.request_timeout_secs, DEFAULT_REQUEST_TIMEOUT_SECS);
+        assert_eq!((b.bot.as_str(), b.games), ("search 100", 0));
+        assert_eq!(
+            (b.restart, b.max_restarts),
+            (RestartPolicy::OnError, Some(3))
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let config: FleetConfig =
+            serde_json::from_str(r#"{ "players": [{ "name": "a" }, { "name": "a" }] }"#).unwrap();
+
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+pub mod config;
+pub mod player;
+
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    common::{
+        logging,
+        session::{SessionSummary, Shutdown, ShutdownPolicy},
+    },
+    record::snapshot::GameSnapshot,
+};
+
+use self::{
+    config::FleetConfig,
+    player::{PlayerState, PlayerStatus},
+};
+
+static POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Runs every player of the fleet in its own thread until all of them are done,
+/// logging a status report every `report_interval_secs`.
+/// Returns the summary of the whole fleet and the final status of every player.
+pub fn run(
+    config: &FleetConfig,
+    shutdown: &Shutdown,
+    policy: ShutdownPolicy,
+) -> (SessionSummary, Vec<PlayerStatus>) {
+    let statuses: Vec<Mutex<PlayerStatus>> =
+        config.players.iter().map(|_| Mutex::default()).collect();
+    let report_interval = Duration::from_secs(config.report_interval_secs);
+    let unowned = GameSnapshot::find_all(Path::new(&config.snapshots_dir)).len();
+    if unowned > 0 {
+        logging::warning!(
+            "{unowned} snapshots in {} aren't resumed, move them into {}/<player name>",
+            config.snapshots_dir,
+            config.snapshots_dir
+        );
+    }
+
+    thread::scope(|scope| {
+        let players: Vec<_> = config
+            .players
+            .iter()
+            .zip(&statuses)
+            .map(|(player, status)| {
+                scope.spawn(move || player::run(config, player, status, shutdown, policy))
+            })
+            .collect();
+
+        let mut reported = Instant::now();
+        while !players.iter().all(|player| player.is_finished()) {
+            thread::sleep(POLL_INTERVAL);
+            if reported.elapsed() >= report_interval {
+                log_report(config, &statuses);
+                reported = Instant::now();
+            }
+        }
+
+        for (player, status) in players.into_iter().zip(&statuses) {
+            if player.join().is_err() {
+                let mut status = status.lock().unwrap();
+                status.state = PlayerState::Failed;
+                status.summary.errors += 1;
+            }
+        }
+    });
+
+    let statuses: Vec<PlayerStatus> = statuses
+        .into_iter()
+        .map(|status| status.into_inner().unwrap())
+        .collect();
+    let mut summary = SessionSummary::default();
+    for status in &statuses {
+        summary += status.summary;
+    }
+    summary.interrupted |= shutdown.is_requested();
+
+    (summary, statuses)
+}
+
+/// Status table of the fleet, one player per row.
+pub fn report(config: &FleetConfig, statuses: &[PlayerStatus]) -> String {
+    let width = config
+        .players
+        .iter()
+        .map(|p| p.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    let mut report = format!(
+        "{:<width$}  {:<10}  {:>5}  {:>4}  {:>5}  {:>6}  {:>6}  {:>8}  bot\n",
+        "player", "state", "games", "wins", "draws", "losses", "errors", "restarts"
+    );
+    for (player, status) in config.players.iter().zip(statuses) {
+        let summary = status.summary;
+        let _ = writeln!(
+            report,
+            "{:<width$}  {:<10}  {:>5}  {:>4}  {:>5}  {:>6}  {:>6}  {:>8}  {}",
+            player.name,
+            format!("{:?}", status.state),
+            summary.games(),
+            summary.wins,
+            summary.draws,
+            summary.losses,
+            summary.errors,
+            status.restarts,
+            player.bot
+        );
+    }
+
+    report
+}
+
+fn log_report(config: &FleetConfig, statuses: &[Mutex<PlayerStatus>]) {
+    for (player, status) in config.players.iter().zip(statuses) {
+        let status = *status.lock().unwrap();
+        let summary = status.summary;
+        logging::info!(
+            "player {}: {:?}, {} games, {} wins, {} draws, {} losses, {} errors, {} restarts",
+            player.name,
+            status.state,
+            summary.games(),
+            summary.wins,
+            summary.draws,
+            summary.losses,
+            summary.errors,
+            status.restarts
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_player() {
+        let config = FleetConfig::of_bot("random", 2, 1);
+        let mut finished = PlayerStatus {
+            state: PlayerState::Finished,
+            ..Default::default()
+        };
+        finished.summary.wins = 1;
+
+        let report = report(&config, &[finished, PlayerStatus::default()]);
+        let lines: Vec<Vec<&str>> = report
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            ["player-1", "Finished", "1", "1", "0", "0", "0", "0", "random"]
+        );
+        assert_eq!(lines[2][..2], ["player-2", "Starting"]);
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{
+    bots,
+    common::{
+        error::{CoreError, GameProcessError},
+        game_process::{GameProcess, GameResult},
+        logging::{self, LogContext},
+        session::{SessionSummary, Shutdown, ShutdownPolicy},
+    },
+    hooks::api_hooks::ApiHooks,
+    metrics::observer::MetricsObserver,
+    record::{recorder::GameRecorder, snapshot::GameSnapshot},
+};
+
+use super::config::{FleetConfig, PlayerConfig, RestartPolicy};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayerState {
+    #[default]
+    Starting,
+    Playing,
+    Restarting,
+    Finished,
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerStatus {
+    pub state: PlayerState,
+    pub summary: SessionSummary,
+    pub restarts: usize,
+}
+
+/// Plays the games of one fleet player, restarting after errors per its policy.
+/// The first games resume the player's snapshots, oldest first.
+pub fn run(
+    fleet: &FleetConfig,
+    config: &PlayerConfig,
+    status: &Mutex<PlayerStatus>,
+    shutdown: &Shutdown,
+    policy: ShutdownPolicy,
+) {
+    let snapshots_dir = Path::new(&fleet.snapshots_dir).join(&config.name);
+    let mut resume: VecDeque<PathBuf> = GameSnapshot::find_all(&snapshots_dir).into();
+
+    run_games(config, status, shutdown, || {
+        play_game(
+            fleet,
+            config,
+            &snapshots_dir,
+            resume.pop_front(),
+            shutdown,
+            policy,
+        )
+    });
+}
+
+/// Calls `play` for every game of the player until it's done or fails for good.
+fn run_games(
+    config: &PlayerConfig,
+    status: &Mutex<PlayerStatus>,
+    shutdown: &Shutdown,
+    mut play: impl FnMut() -> Result<GameResult, GameProcessError>,
+) {
+    let mut errors_in_row = 0;
+
+    let state = loop {
+        let played = status.lock().unwrap().summary.games();
+        if shutdown.is_requested() || (config.games != 0 && played >= config.games) {
+            break PlayerState::Finished;
+        }
+
+        status.lock().unwrap().state = PlayerState::Playing;
+        let result = play();
+        logging::set_context(LogContext::default());
+        status.lock().unwrap().summary.add(&result);
+
+        match result {
+            Ok(_) => errors_in_row = 0,
+            Err(GameProcessError::Interrupted) => break PlayerState::Finished,
+            Err(e) => {
+                errors_in_row += 1;
+                let restarts = status.lock().unwrap().restarts;
+                let can_restart = config.restart == RestartPolicy::OnError
+                    && config.max_restarts.is_none_or(|max| restarts < max);
+                if !can_restart {
+                    logging::error!("player {} stopped after error: {e}", config.name);
+                    break PlayerState::Failed;
+                }
+
+                let delay = Duration::from_millis(config.restart_delay_ms) * errors_in_row;
+                logging::warning!(
+                    "player {} restarts in {delay:?} after error: {e}",
+                    config.name
+                );
+                {
+                    let mut status = status.lock().unwrap();
+                    status.state = PlayerState::Restarting;
+                    status.restarts += 1;
+                }
+                shutdown.sleep(delay);
+            }
+        }
+    };
+
+    status.lock().unwrap().state = state;
+}
+
+fn play_game(
+    fleet: &FleetConfig,
+    config: &PlayerConfig,
+    snapshots_dir: &Path,
+    resume: Option<PathBuf>,
+    shutdown: &Shutdown,
+    policy: ShutdownPolicy,
+) -> Result<GameResult, GameProcessError> {
+    let bot = bots::from_spec(&config.bot).map_err(GameProcessError::Bot)?;
+    let hooks = ApiHooks::with_server(&config.server)
+        .with_name(&config.name)
+        .with_timeout(Duration::from_secs(config.request_timeout_secs))
+        .with_shutdown(shutdown.clone(), policy);
+    let hooks = Box::new(hooks);
+
+    let snapshot = resume.and_then(|path| {
+        GameSnapshot::load(&path)
+            .inspect_err(|e| logging::error!("can't load snapshot {}: {e}", path.display()))
+            .ok()
+    });
+    let player = match snapshot {
+        Some(snapshot) => GameProcess::resume(bot, hooks, &snapshot)?,
+        None => GameProcess::new(bot, hooks),
+    };
+    let mut player = player
+        .with_pondering(config.pondering)
+        .with_snapshots_dir(snapshots_dir.to_owned())
+        .with_shutdown(shutdown.clone(), policy)
+        .with_observer(Box::new(GameRecorder::new(
+            fleet.records_dir.clone().into(),
+        )))
+        .with_observer(Box::new(MetricsObserver));
+
+    // the game process logs its result and errors itself
+    panic::catch_unwind(AssertUnwindSafe(|| player.run()))
+        .unwrap_or_else(|_| Err(GameProcessError::Core(CoreError::of_panic())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::common::{
+        bot::{Coords, Turn},
+        error::HookError,
+        game_process::PlayInfo,
+        hooks::Hooks,
+    };
+
+    use super::*;
+
+    struct FailingHooks;
+
+    impl Hooks for FailingHooks {
+        fn init(&mut self) -> Result<PlayInfo, HookError> {
+            Err(HookError {
+                message: "server is down".to_owned(),
+            })
+        }
+
+        fn wait_for_turn(&mut self) -> Result<Option<Turn>, HookError> {
+            unreachable!()
+        }
+
+        fn make_turn(&mut self, _turn: &Coords) -> Result<(), HookError> {
+            unreachable!()
+        }
+    }
+
+    fn run_failing(config: &str) -> (PlayerStatus, usize) {
+        let config: PlayerConfig = serde_json::from_str(config).unwrap();
+        let status = Mutex::default();
+        let mut games = 0;
+
+        run_games(&config, &status, &Shutdown::default(), || {
+            games += 1;
+            let bot = bots::from_spec("random").map_err(GameProcessError::Bot)?;
+            GameProcess::new(bot, Box::new(FailingHooks)).run()
+        });
+
+        (status.into_inner().unwrap(), games)
+    }
+
+    #[test]
+    fn stops_after_error_without_restarts() {
+        let (status, games) = run_failing(r#"{ "name": "a", "games": 0 }"#);
+
+        assert_eq!(status.state, PlayerState::Failed);
+        assert_eq!((status.restarts, status.summary.errors, games), (0, 1, 1));
+    }
+
+    #[test]
+    fn restarts_with_growing_delay_up_to_limit() {
+        let started = Instant::now();
+        let (status, games) = run_failing(
+            r#"{ "name": "a", "games": 0, "restart": "on_error", "max_restarts": 2,
+                 "restart_delay_ms": 20 }"#,
+        );
+
+        assert_eq!(status.state, PlayerState::Failed);
+        assert_eq!((status.restarts, status.summary.errors, games), (2, 3, 3));
+        // 20 ms after the first error in a row, 40 ms after the second
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+}
@@ -1,21 +1,39 @@
+use std::time::Duration;
+
 use crate::{
     api_client::{
-        dto::{CoordsDto, RegisterInfoDto, SideDto, StateDto, TurnDto},
-        methods::{get_player_game, get_players, post_game_turn, post_mathchmaking_queue},
+        dto::{OpeningDto, RegisterInfoDto, StateDto},
+        mapping::{ToDto, ToEntity, TryToEntity},
+        methods::{
+            get_player_game, get_players, post_game_opening, post_game_side, post_game_turn,
+            post_mathchmaking_queue,
+        },
     },
     common::{
         bot::{Coords, Side, Size, Turn},
         error::HookError,
         game_process::PlayInfo,
-        hooks::Hooks,
+        hooks::{GameStatus, Hooks, OpeningStep},
+        logging,
+        opening::SideChoice,
+        rules::RuleSet,
+        session::{Shutdown, ShutdownPolicy},
     },
 };
 
 static POLLING_TIMEOUT: usize = 3000;
+static DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(3600);
+pub static DEFAULT_SERVER: &str = "http://localhost:8080";
+pub static DEFAULT_NAME: &str = "test_bot";
 
 pub struct ApiHooks {
+    server: String,
+    name: String,
+    timeout: Duration,
     player_id: Option<String>,
     side: Option<Side>,
+    shutdown: Shutdown,
+    shutdown_policy: ShutdownPolicy,
 }
 
 impl Hooks for ApiHooks {
@@ -23,6 +41,10 @@ impl Hooks for ApiHooks {
         self.register()?;
         self.wait_for_game(true)?;
         let game_dto = self.get_game(false)?;
+        let rules = match &game_dto.settings.rules {
+            Some(rules) => RuleSet::parse(rules).map_err(|e| HookError { message: e.message })?,
+            None => RuleSet::STANDARD,
+        };
 
         Ok(PlayInfo {
             field_size: Size {
@@ -30,7 +52,10 @@ impl Hooks for ApiHooks {
                 height: game_dto.settings.height,
             },
             win_condition: game_dto.settings.win_condition,
+            rules,
             side: self.side.unwrap(),
+            player_id: self.player_id.clone(),
+            game_id: game_dto.id,
         })
     }
 
@@ -42,37 +67,156 @@ impl Hooks for ApiHooks {
     fn make_turn(&mut self, coords: &Coords) -> Result<(), HookError> {
         self.make_turn(coords)
     }
+
+    fn propose_opening(&mut self, stones: &[Coords]) -> Result<(), HookError> {
+        let opening = OpeningDto {
+            stones: stones.iter().map(|coords| coords.to_dto()).collect(),
+        };
+        post_game_opening(
+            &self.server,
+            self.timeout,
+            self.player_id.as_ref().unwrap(),
+            opening,
+        )
+        .map_err(|e| HookError {
+            message: e.to_string(),
+        })
+    }
+
+    fn wait_for_opening(&mut self) -> Result<OpeningStep, HookError> {
+        loop {
+            if self.shutdown.is_requested() && self.shutdown_policy == ShutdownPolicy::Resign {
+                return Err(HookError::of_interrupted());
+            }
+            logging::debug!("waiting for opening...");
+            let game_dto = self.get_game(true)?;
+
+            match (game_dto.state, self.side.unwrap()) {
+                (StateDto::XSideChoice, Side::X) | (StateDto::OSideChoice, Side::O) => {
+                    let field = game_dto
+                        .field
+                        .try_to_entity()
+                        .map_err(|e| HookError { message: e.message })?;
+                    return Ok(OpeningStep::Stones(field));
+                }
+                (StateDto::XMove | StateDto::OMove | StateDto::Finished, _) => {
+                    // the opponent took a side, ours may have changed with it
+                    self.wait_for_game(false)?;
+                    return Ok(OpeningStep::SideTaken(self.side.unwrap().opposite()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn choose_side(&mut self, choice: &SideChoice) -> Result<(), HookError> {
+        let player_id = self.player_id.as_ref().unwrap();
+        post_game_side(&self.server, self.timeout, player_id, choice.to_dto()).map_err(|e| {
+            HookError {
+                message: e.to_string(),
+            }
+        })?;
+        if let SideChoice::Take(side) = choice {
+            self.side = Some(*side);
+        }
+
+        Ok(())
+    }
+
+    fn reattach(&mut self, play_info: &PlayInfo) -> Result<GameStatus, HookError> {
+        let player_id = play_info.player_id.clone().ok_or_else(|| HookError {
+            message: "Can't reattach without a player id".to_owned(),
+        })?;
+        logging::info!("reattaching to player {player_id}");
+        self.player_id = Some(player_id);
+        self.side = Some(play_info.side);
+
+        let game_dto = self.get_game(false)?;
+        Ok(GameStatus {
+            field: game_dto
+                .field
+                .try_to_entity()
+                .map_err(|e| HookError { message: e.message })?,
+            next_side: match game_dto.state {
+                StateDto::XMove => Some(Side::X),
+                StateDto::OMove => Some(Side::O),
+                StateDto::Finished => None,
+                StateDto::Opening | StateDto::XSideChoice | StateDto::OSideChoice => {
+                    return Err(HookError {
+                        message: "Can't reattach during the opening".to_owned(),
+                    })
+                }
+            },
+        })
+    }
 }
 
 impl ApiHooks {
-    pub fn new() -> Self {
+    pub fn with_server(server: &str) -> Self {
         ApiHooks {
+            server: server.to_owned(),
+            name: DEFAULT_NAME.to_owned(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
             player_id: None,
             side: None,
+            shutdown: Shutdown::default(),
+            shutdown_policy: ShutdownPolicy::default(),
         }
     }
 
+    /// Name to register with in the matchmaking queue.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Gives up on requests the server takes longer than `timeout` to answer,
+    /// long polling included.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Stops waiting for a game on shutdown, and for opponent's turn
+    /// too if the policy is to resign.
+    pub fn with_shutdown(mut self, shutdown: Shutdown, policy: ShutdownPolicy) -> Self {
+        self.shutdown = shutdown;
+        self.shutdown_policy = policy;
+        self
+    }
+
     fn register(&mut self) -> Result<(), HookError> {
-        let player_dto = post_mathchmaking_queue(RegisterInfoDto {
-            name: "test_bot".to_owned(),
-        })
+        let player_dto = post_mathchmaking_queue(
+            &self.server,
+            self.timeout,
+            RegisterInfoDto {
+                name: self.name.clone(),
+            },
+        )
         .map_err(|e| HookError {
             message: e.to_string(),
         })?;
-        println!("player registered: {:?}", player_dto.id);
+        logging::info!("player registered: {}", player_dto.id);
         self.player_id = Some(player_dto.id);
         Ok(())
     }
 
     fn wait_for_game(&mut self, polling: bool) -> Result<(), HookError> {
         loop {
-            println!("waiting for game...");
-            let player_dto =
-                get_players(self.player_id.as_ref().unwrap(), polling, POLLING_TIMEOUT).map_err(
-                    |e| HookError {
-                        message: e.to_string(),
-                    },
-                )?;
+            if self.shutdown.is_requested() {
+                return Err(HookError::of_interrupted());
+            }
+            logging::debug!("waiting for game...");
+            let player_dto = get_players(
+                &self.server,
+                self.timeout,
+                self.player_id.as_ref().unwrap(),
+                polling,
+                POLLING_TIMEOUT,
+            )
+            .map_err(|e| HookError {
+                message: e.to_string(),
+            })?;
 
             if let Some(side) = player_dto.side {
                 self.side = Some(side.to_entity());
@@ -80,113 +224,328 @@ impl ApiHooks {
             }
         }
 
-        println!("game found!");
-        println!("side: {:#?}", self.side.unwrap());
+        logging::info!("game found, playing {:?}", self.side.unwrap());
         Ok(())
     }
 
     fn wait_for_turn(&mut self) -> Result<crate::api_client::dto::GameDto, HookError> {
         loop {
-            println!("waiting for turn...");
-            let game_dto = self.get_game(true).unwrap();
+            if self.shutdown.is_requested() && self.shutdown_policy == ShutdownPolicy::Resign {
+                return Err(HookError::of_interrupted());
+            }
+            logging::debug!("waiting for turn...");
+            let game_dto = self.get_game(true)?;
 
             if (game_dto.state == StateDto::Finished)
                 || (game_dto.state == to_state_dto(self.side.unwrap()))
             {
-                println!("its our turn now!");
+                logging::debug!("its our turn now!");
                 return Ok(game_dto);
             }
         }
     }
 
     fn get_game(&mut self, polling: bool) -> Result<crate::api_client::dto::GameDto, HookError> {
-        let game_dto = get_player_game(self.player_id.as_ref().unwrap(), polling, POLLING_TIMEOUT)
-            .map_err(|e| HookError {
-                message: e.to_string(),
-            })?;
+        let game_dto = get_player_game(
+            &self.server,
+            self.timeout,
+            self.player_id.as_ref().unwrap(),
+            polling,
+            POLLING_TIMEOUT,
+        )
+        .map_err(|e| HookError {
+            message: e.to_string(),
+        })?;
 
-        // println!("game received!");
-        // println!("game: {game_dto:#?}");
+        logging::debug!("game received: {game_dto:?}");
         Ok(game_dto)
     }
 
     fn make_turn(&mut self, coords: &Coords) -> Result<(), HookError> {
-        post_game_turn(self.player_id.as_ref().unwrap(), coords.to_dto()).map_err(|e| {
+        let player_id = self.player_id.as_ref().unwrap();
+        post_game_turn(&self.server, self.timeout, player_id, coords.to_dto()).map_err(|e| {
             HookError {
                 message: e.to_string(),
             }
         })?;
 
-        println!("turn maked: {coords:?}");
+        logging::info!("turn made: {}", coords.to_algebraic());
         Ok(())
     }
 }
 
-trait ToDto<Dto> {
-    fn to_dto(&self) -> Dto;
+fn to_state_dto(side: Side) -> StateDto {
+    match side {
+        Side::X => StateDto::XMove,
+        Side::O => StateDto::OMove,
+    }
 }
 
-trait ToEntity<Entity> {
-    fn to_entity(&self) -> Entity;
-}
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
 
-impl ToEntity<Side> for SideDto {
-    fn to_entity(&self) -> Side {
-        match self {
-            SideDto::X => Side::X,
-            SideDto::O => Side::O,
-        }
+    use crate::{
+        api_client::dto::{
+            CellDto, CoordsDto, FieldDto, GameDto, GameSettingsDto, PlayerDto, ResultDto, SideDto,
+            StateDto, StatusDto, TurnDto,
+        },
+        common::{
+            bot::{Coords, Side, Turn},
+            hooks::{Hooks, OpeningStep},
+            session::{Shutdown, ShutdownPolicy},
+        },
+        hooks::mock_server::{MockResponse, MockServer},
+    };
+
+    use super::ApiHooks;
+
+    static PLAYER_ID: &str = "player-1";
+
+    fn player(side: Option<SideDto>) -> MockResponse {
+        MockResponse::json(&PlayerDto {
+            id: PLAYER_ID.to_owned(),
+            status: match side {
+                Some(_) => StatusDto::InGame,
+                None => StatusDto::InQueue,
+            },
+            side,
+        })
     }
-}
 
-impl ToDto<SideDto> for Side {
-    fn to_dto(&self) -> SideDto {
-        match self {
-            Side::X => SideDto::X,
-            Side::O => SideDto::O,
-        }
+    fn game(state: StateDto, last_turn: Option<(SideDto, isize, isize)>) -> MockResponse {
+        MockResponse::json(&GameDto {
+            id: Some("game-1".to_owned()),
+            field: FieldDto {
+                cells: (0..3)
+                    .map(|_| (0..3).map(|_| CellDto::Empty).collect())
+                    .collect(),
+                width: 3,
+                height: 3,
+                last_move: None,
+            },
+            settings: GameSettingsDto {
+                width: 3,
+                height: 3,
+                win_condition: 3,
+                rules: None,
+            },
+            result: match state {
+                StateDto::Finished => Some(ResultDto::Draw),
+                _ => None,
+            },
+            state,
+            current_turn: 0,
+            last_turn: last_turn.map(|(side, x, y)| TurnDto {
+                side,
+                coords: CoordsDto { x, y },
+            }),
+        })
     }
-}
 
-impl ToEntity<Coords> for CoordsDto {
-    fn to_entity(&self) -> Coords {
-        Coords {
-            x: self.x,
-            y: self.y,
+    fn joined_hooks(server: &MockServer, side: Side) -> ApiHooks {
+        ApiHooks {
+            player_id: Some(PLAYER_ID.to_owned()),
+            side: Some(side),
+            ..ApiHooks::with_server(server.url())
         }
     }
-}
 
-impl ToDto<CoordsDto> for Coords {
-    fn to_dto(&self) -> CoordsDto {
-        CoordsDto {
-            x: self.x,
-            y: self.y,
+    #[test]
+    fn init_registers_and_waits_for_game() {
+        let server = MockServer::start(vec![
+            player(None),
+            player(None),
+            player(Some(SideDto::O)),
+            game(StateDto::XMove, None),
+        ]);
+        let mut hooks = ApiHooks::with_server(server.url());
+
+        let play_info = hooks.init().unwrap();
+
+        assert_eq!(play_info.side, Side::O);
+        assert_eq!(play_info.game_id.as_deref(), Some("game-1"));
+        assert_eq!(play_info.win_condition, 3);
+        assert_eq!(play_info.field_size.width, 3);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/api/v1/matchmaking/queue");
+        assert_eq!(requests[0].body, r#"{"name":"test_bot"}"#);
+        for request in &requests[1..3] {
+            assert_eq!(request.method, "GET");
+            assert_eq!(request.path, "/api/v1/players/player-1");
+            assert_eq!(request.query("longPollingEnabled"), Some("true"));
+            assert_eq!(request.query("longPollingTimeout"), Some("3000"));
         }
+        assert_eq!(requests[3].path, "/api/v1/players/player-1/game");
+        assert_eq!(requests[3].query("longPollingEnabled"), Some("false"));
     }
-}
 
-impl ToEntity<Turn> for TurnDto {
-    fn to_entity(&self) -> Turn {
-        Turn {
-            coords: self.coords.to_entity(),
-            side: self.side.to_entity(),
-        }
+    #[test]
+    fn init_fails_when_queue_is_unavailable() {
+        let server = MockServer::start(vec![MockResponse::error(503, "queue is closed")]);
+        let mut hooks = ApiHooks::with_server(server.url());
+
+        assert!(hooks.init().is_err());
+        assert_eq!(server.requests().len(), 1);
     }
-}
 
-impl ToDto<TurnDto> for Turn {
-    fn to_dto(&self) -> TurnDto {
-        TurnDto {
-            coords: self.coords.to_dto(),
-            side: self.side.to_dto(),
+    #[test]
+    fn init_fails_on_malformed_player() {
+        let server = MockServer::start(vec![MockResponse::raw(r#"{"id":"player-1"}"#)]);
+        let mut hooks = ApiHooks::with_server(server.url());
+
+        assert!(hooks.init().is_err());
+    }
+
+    #[test]
+    fn wait_for_turn_polls_until_our_move() {
+        let server = MockServer::start(vec![
+            game(StateDto::XMove, None),
+            game(StateDto::XMove, None),
+            game(StateDto::OMove, Some((SideDto::X, 1, 2))),
+        ]);
+        let mut hooks = joined_hooks(&server, Side::O);
+
+        let turn = Hooks::wait_for_turn(&mut hooks).unwrap();
+
+        let expected = Turn {
+            coords: Coords { x: 1, y: 2 },
+            side: Side::X,
+        };
+        assert_eq!(turn, Some(expected));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        for request in requests {
+            assert_eq!(request.method, "GET");
+            assert_eq!(request.path, "/api/v1/players/player-1/game");
+            assert_eq!(request.query("longPollingEnabled"), Some("true"));
         }
     }
-}
 
-fn to_state_dto(side: Side) -> StateDto {
-    match side {
-        Side::X => StateDto::XMove,
-        Side::O => StateDto::OMove,
+    #[test]
+    fn wait_for_turn_returns_nothing_on_first_move() {
+        let server = MockServer::start(vec![game(StateDto::XMove, None)]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        assert!(Hooks::wait_for_turn(&mut hooks).unwrap().is_none());
+    }
+
+    #[test]
+    fn wait_for_turn_stops_on_finished_game() {
+        let server = MockServer::start(vec![
+            game(StateDto::OMove, None),
+            game(StateDto::Finished, Some((SideDto::O, 0, 0))),
+        ]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        let turn = Hooks::wait_for_turn(&mut hooks).unwrap().unwrap();
+
+        assert_eq!(turn.side, Side::O);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn wait_for_turn_fails_on_server_error() {
+        let server = MockServer::start(vec![
+            game(StateDto::OMove, None),
+            MockResponse::error(500, "game is lost"),
+        ]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        assert!(Hooks::wait_for_turn(&mut hooks).is_err());
+        assert_eq!(server.requests().len(), 5);
+    }
+
+    #[test]
+    fn wait_for_turn_retries_transient_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::error(503, "restarting"),
+            game(StateDto::XMove, Some((SideDto::O, 1, 1))),
+        ]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        let turn = Hooks::wait_for_turn(&mut hooks).unwrap().unwrap();
+
+        assert_eq!(turn.coords, Coords { x: 1, y: 1 });
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn wait_for_turn_fails_on_slow_server() {
+        let slow = game(StateDto::XMove, None).with_delay(Duration::from_millis(1000));
+        let server = MockServer::start(vec![slow]);
+        let mut hooks = joined_hooks(&server, Side::X).with_timeout(Duration::from_millis(100));
+        let started = Instant::now();
+
+        let error = Hooks::wait_for_turn(&mut hooks).unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        assert!(error.message.contains("timed out"), "{}", error.message);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn make_turn_fails_on_slow_server() {
+        let server = MockServer::start(vec![
+            MockResponse::raw("").with_delay(Duration::from_millis(1000))
+        ]);
+        let mut hooks = joined_hooks(&server, Side::X).with_timeout(Duration::from_millis(100));
+
+        assert!(Hooks::make_turn(&mut hooks, &Coords { x: 0, y: 0 }).is_err());
+    }
+
+    #[test]
+    fn wait_for_turn_stops_when_resigning() {
+        let server = MockServer::start(vec![game(StateDto::OMove, None)]);
+        let shutdown = Shutdown::default();
+        let mut hooks =
+            joined_hooks(&server, Side::X).with_shutdown(shutdown.clone(), ShutdownPolicy::Resign);
+        shutdown.request();
+
+        assert!(Hooks::wait_for_turn(&mut hooks).is_err());
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn wait_for_opening_follows_side_choice() {
+        let server = MockServer::start(vec![
+            game(StateDto::Opening, None),
+            game(StateDto::OSideChoice, None),
+            game(StateDto::XMove, None),
+            player(Some(SideDto::X)),
+        ]);
+        let mut hooks = joined_hooks(&server, Side::O);
+
+        let step = Hooks::wait_for_opening(&mut hooks).unwrap();
+        assert!(matches!(step, OpeningStep::Stones(_)));
+        let step = Hooks::wait_for_opening(&mut hooks).unwrap();
+        assert!(matches!(step, OpeningStep::SideTaken(Side::O)));
+        assert_eq!(hooks.side, Some(Side::X));
+        assert_eq!(server.requests()[3].path, "/api/v1/players/player-1");
+    }
+
+    #[test]
+    fn make_turn_posts_coords() {
+        let server = MockServer::start(vec![game(StateDto::OMove, Some((SideDto::X, 2, 1)))]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        Hooks::make_turn(&mut hooks, &Coords { x: 2, y: 1 }).unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/api/v1/players/player-1/game/turn");
+        assert_eq!(requests[0].body, r#"{"x":2,"y":1}"#);
+    }
+
+    #[test]
+    fn make_turn_fails_on_rejected_turn() {
+        let server = MockServer::start(vec![MockResponse::error(409, "cell is filled")]);
+        let mut hooks = joined_hooks(&server, Side::X);
+
+        assert!(Hooks::make_turn(&mut hooks, &Coords { x: 0, y: 0 }).is_err());
     }
 }
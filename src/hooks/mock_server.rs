@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tiny_http::{Header, Response, Server};
+
+pub struct MockServer {
+    url: String,
+    server: Arc<Server>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+pub struct MockResponse {
+    status: u16,
+    body: String,
+    delay: Duration,
+}
+
+impl MockServer {
+    pub fn start(script: Vec<MockResponse>) -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let handle = {
+            let server = server.clone();
+            let requests = requests.clone();
+            thread::spawn(move || {
+                let mut script = script.into_iter();
+                for mut request in server.incoming_requests() {
+                    let url = request.url().to_owned();
+                    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+                    let mut body = String::new();
+                    request.as_reader().read_to_string(&mut body).unwrap();
+
+                    requests.lock().unwrap().push(RecordedRequest {
+                        method: request.method().to_string(),
+                        path: path.to_owned(),
+                        query: query
+                            .split('&')
+                            .filter_map(|pair| pair.split_once('='))
+                            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                            .collect(),
+                        body,
+                    });
+
+                    let response = script
+                        .next()
+                        .unwrap_or_else(|| MockResponse::error(500, "unexpected request"));
+                    thread::sleep(response.delay);
+                    let content_type =
+                        Header::from_bytes("Content-Type", "application/json").unwrap();
+                    let _ = request.respond(
+                        Response::from_string(response.body)
+                            .with_status_code(response.status)
+                            .with_header(content_type),
+                    );
+                }
+            })
+        };
+
+        MockServer {
+            url,
+            server,
+            requests,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl MockResponse {
+    pub fn json(body: &impl Serialize) -> Self {
+        MockResponse {
+            status: 200,
+            body: serde_json::to_string(body).unwrap(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn raw(body: &str) -> Self {
+        MockResponse {
+            status: 200,
+            body: body.to_owned(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        MockResponse {
+            status,
+            body: serde_json::json!({ "message": message }).to_string(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Answers only after `delay`.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+impl RecordedRequest {
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query.get(key).map(String::as_str)
+    }
+}
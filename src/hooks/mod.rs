@@ -1 +1,3 @@
-pub mod api_hooks;
\ No newline at end of file
+pub mod api_hooks;
+#[cfg(test)]
+pub mod mock_server;
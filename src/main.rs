@@ -1,27 +1,334 @@
-use std::thread::{self, JoinHandle};
+use std::{env, io, path::Path, process};
 
-use common::game_process::GameProcess;
+use common::{
+    bot::{Field, Size, Topology},
+    cube::CUBE_SIZES,
+    game_process::PlayInfo,
+    logging,
+    rules::{GameSettings, RuleSet},
+    session::{Shutdown, ShutdownPolicy, EXIT_ERRORS, EXIT_INTERRUPTED},
+};
 
-use crate::{bots::random_bot::RandomBot, hooks::api_hooks::ApiHooks};
+use crate::{
+    arbiter::{cube_match, local_match},
+    fleet::config::FleetConfig,
+    metrics::exporter,
+    protocol::gomocup::GomocupEngine,
+    record::game_record::GameRecord,
+    replay::viewer::{render_field, ReplayViewer},
+};
 
 mod api_client;
+mod arbiter;
 mod bots;
 mod common;
+mod fleet;
 mod hooks;
+mod metrics;
+mod protocol;
+mod record;
+mod replay;
+mod server;
+
+static DEFAULT_BOT: &str = "random";
+static DEFAULT_GAMES: usize = 1;
+static PLAYERS: usize = 2;
+static ANALYSIS_CANDIDATES: usize = 5;
+static DEFAULT_MATCH_GAMES: usize = 10;
+static DEFAULT_SERVER_ADDR: &str = "localhost:8080";
+static DEFAULT_SERVER_SETTINGS: GameSettings = GameSettings {
+    field_size: Size {
+        width: 3,
+        height: 3,
+    },
+    win_condition: 3,
+    rules: RuleSet::STANDARD,
+};
 
 fn main() {
-    let t1 = start_player_thread();
-    let t2 = start_player_thread();
+    logging::init_from_env();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mode = args.first().map(String::as_str).unwrap_or("play");
+    let bot_spec = args.get(1).cloned().unwrap_or(DEFAULT_BOT.to_owned());
+
+    match mode {
+        "play" => process::exit(run_players(bot_spec, &args[1..])),
+        "fleet" => process::exit(run_fleet(&args[1..])),
+        "gomocup" => run_gomocup(bot_spec, args.get(2..).unwrap_or_default()),
+        "server" => run_server(&args[1..]),
+        "replay" => run_replay(&args[1..]),
+        "analyze" => run_analyze(&args[1..]),
+        "match" => run_match(&args[1..]),
+        "qubic" => run_qubic(&args[1..]),
+        _ => println!(
+            "usage: tic-tac-toe-bot play [bot] [games]\n       \
+             tic-tac-toe-bot fleet <config.json>\n       \
+             tic-tac-toe-bot gomocup [bot] [win_condition]\n       \
+             tic-tac-toe-bot server [addr] [width] [height] [win_condition] [rules]\n       \
+             tic-tac-toe-bot replay <record.json|record.txt> [bot]\n       \
+             tic-tac-toe-bot analyze <position> [bot] [rules]\n       \
+             tic-tac-toe-bot match <bot> <bot> [games] [width] [height] [win_condition] [rules]\
+             \n       tic-tac-toe-bot qubic <bot> <bot> [games] [size]"
+        ),
+    }
+}
+
+fn run_players(bot_spec: String, args: &[String]) -> i32 {
+    let games = args
+        .get(1)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_GAMES);
+
+    run_session(&FleetConfig::of_bot(&bot_spec, PLAYERS, games))
+}
+
+fn run_fleet(args: &[String]) -> i32 {
+    let Some(path) = args.first() else {
+        println!("usage: tic-tac-toe-bot fleet <config.json>");
+        return EXIT_ERRORS;
+    };
+    match FleetConfig::load(Path::new(path)) {
+        Ok(config) => run_session(&config),
+        Err(e) => {
+            println!("can't load fleet config {path}: {e}");
+            EXIT_ERRORS
+        }
+    }
+}
+
+fn run_session(config: &FleetConfig) -> i32 {
+    let policy = env::var("SHUTDOWN_POLICY")
+        .ok()
+        .and_then(|value| ShutdownPolicy::parse(&value))
+        .unwrap_or_default();
+
+    if let Ok(addr) = env::var("METRICS_ADDR") {
+        if let Err(e) = exporter::serve(&addr) {
+            logging::error!("can't serve metrics: {}", e.message);
+        }
+    }
+
+    let shutdown = Shutdown::default();
+    let handler_shutdown = shutdown.clone();
+    let handler = ctrlc::set_handler(move || {
+        if handler_shutdown.is_requested() {
+            logging::error!("shutdown requested twice, exiting immediately");
+            logging::flush();
+            process::exit(EXIT_INTERRUPTED);
+        }
+        logging::info!("shutdown requested, policy: {policy:?}");
+        handler_shutdown.request();
+    });
+    if let Err(e) = handler {
+        logging::warning!("can't handle signals: {e}");
+    }
+
+    let (summary, statuses) = fleet::run(config, &shutdown, policy);
+
+    logging::info!(
+        "session finished: {} games, {} wins, {} draws, {} losses, {} errors{}",
+        summary.games(),
+        summary.wins,
+        summary.draws,
+        summary.losses,
+        summary.errors,
+        if summary.interrupted {
+            ", interrupted"
+        } else {
+            ""
+        }
+    );
+    logging::flush();
+    print!("{}", fleet::report(config, &statuses));
+
+    summary.exit_code()
+}
+
+fn run_gomocup(bot_spec: String, args: &[String]) {
+    let bot = match bots::from_spec(&bot_spec) {
+        Ok(bot) => bot,
+        Err(e) => return println!("ERROR can't create bot: {}", e.message),
+    };
 
-    t1.join().unwrap();
-    t2.join().unwrap();
+    let mut engine = GomocupEngine::new(bot);
+    if let Some(win_condition) = args.first() {
+        match win_condition.parse() {
+            Ok(win_condition) if win_condition > 0 => {
+                engine = engine.with_win_condition(win_condition)
+            }
+            _ => return println!("ERROR wrong win condition {win_condition}"),
+        }
+    }
+    if let Err(e) = engine.run(io::stdin().lock(), io::stdout().lock()) {
+        println!("ERROR {e}");
+    }
 }
 
-fn start_player_thread() -> JoinHandle<()> {
-    thread::spawn(|| {
-        let bot = Box::new(RandomBot {});
-        let hooks = Box::new(ApiHooks::new());
-        let mut player = GameProcess::new(bot, hooks);
-        player.run().unwrap();
-    })
+fn run_server(args: &[String]) {
+    let addr = args
+        .first()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_SERVER_ADDR);
+    let mut settings = DEFAULT_SERVER_SETTINGS;
+    let arg = |i: usize| args.get(i).and_then(|value| value.parse().ok());
+    settings.field_size.width = arg(1).unwrap_or(settings.field_size.width);
+    settings.field_size.height = arg(2).unwrap_or(settings.field_size.height);
+    settings.win_condition = arg(3).unwrap_or(settings.win_condition);
+    if let Some(rules) = args.get(4) {
+        match RuleSet::parse(rules) {
+            Ok(rules) => settings.rules = rules,
+            Err(e) => return println!("{}", e.message),
+        }
+    }
+    if let Err(e) = settings.rules.check_field_size(settings.field_size) {
+        return println!("{}", e.message);
+    }
+    if settings.rules.topology == Topology::Infinite {
+        // the API sends whole fields, which infinite ones aren't
+        return println!("the server doesn't host infinite fields");
+    }
+
+    if let Err(e) = server::game_server::run(addr, settings) {
+        logging::error!("server failed: {}", e.message);
+    }
+}
+
+fn run_replay(args: &[String]) {
+    let Some(path) = args.first() else {
+        return println!("usage: tic-tac-toe-bot replay <record.json|record.txt> [bot]");
+    };
+    let record = match GameRecord::load(Path::new(path)) {
+        Ok(record) => record,
+        Err(e) => return println!("can't load record {path}: {e}"),
+    };
+    let bot = match args.get(1).map(|spec| bots::from_spec(spec)).transpose() {
+        Ok(bot) => bot,
+        Err(e) => return println!("can't create bot: {}", e.message),
+    };
+
+    let mut viewer = match ReplayViewer::new(record, bot) {
+        Ok(viewer) => viewer,
+        Err(e) => return println!("can't start bot: {}", e.message),
+    };
+    if let Err(e) = viewer.run(io::stdin().lock(), io::stdout().lock()) {
+        println!("replay failed: {e}");
+    }
+}
+
+fn run_match(args: &[String]) {
+    let (Some(first), Some(second)) = (args.first(), args.get(1)) else {
+        return println!(
+            "usage: tic-tac-toe-bot match <bot> <bot> [games] [width] [height] [win_condition] \
+             [rules]"
+        );
+    };
+    let bots = bots::from_spec(first).and_then(|first| Ok((first, bots::from_spec(second)?)));
+    let (first, second) = match bots {
+        Ok(bots) => bots,
+        Err(e) => return println!("can't create bot: {}", e.message),
+    };
+
+    let arg = |i: usize| args.get(i).and_then(|value| value.parse().ok());
+    let games = arg(2).unwrap_or(DEFAULT_MATCH_GAMES);
+    let mut settings = DEFAULT_SERVER_SETTINGS;
+    settings.field_size.width = arg(3).unwrap_or(settings.field_size.width);
+    settings.field_size.height = arg(4).unwrap_or(settings.field_size.height);
+    settings.win_condition = arg(5).unwrap_or(settings.win_condition);
+    if let Some(rules) = args.get(6) {
+        match RuleSet::parse(rules) {
+            Ok(rules) => settings.rules = rules,
+            Err(e) => return println!("{}", e.message),
+        }
+    }
+    if let Err(e) = settings.rules.check_field_size(settings.field_size) {
+        return println!("{}", e.message);
+    }
+
+    let score = local_match::run_match(first.as_ref(), second.as_ref(), games, settings, |game| {
+        println!("{game}")
+    });
+    println!(
+        "first: {} wins, {} draws, {} losses",
+        score.wins, score.draws, score.losses
+    );
+}
+
+fn run_qubic(args: &[String]) {
+    let (Some(first), Some(second)) = (args.first(), args.get(1)) else {
+        return println!("usage: tic-tac-toe-bot qubic <bot> <bot> [games] [size]");
+    };
+    let bots =
+        bots::cube_from_spec(first).and_then(|first| Ok((first, bots::cube_from_spec(second)?)));
+    let (first, second) = match bots {
+        Ok(bots) => bots,
+        Err(e) => return println!("can't create bot: {}", e.message),
+    };
+
+    let arg = |i: usize| args.get(i).and_then(|value| value.parse().ok());
+    let games = arg(2).unwrap_or(DEFAULT_MATCH_GAMES);
+    let size = arg(3).unwrap_or(CUBE_SIZES[CUBE_SIZES.len() - 1]);
+    if !CUBE_SIZES.contains(&size) {
+        return println!("cube size must be one of {CUBE_SIZES:?}");
+    }
+
+    let score = cube_match::run_match(first.as_ref(), second.as_ref(), games, size, |game| {
+        println!("{game}")
+    });
+    println!(
+        "first: {} wins, {} draws, {} losses",
+        score.wins, score.draws, score.losses
+    );
+}
+
+fn run_analyze(args: &[String]) {
+    let Some(notation) = args.first() else {
+        return println!("usage: tic-tac-toe-bot analyze <position> [bot] [rules]");
+    };
+    let position = match Field::from_notation(notation) {
+        Ok(position) => position,
+        Err(e) => return println!("{}", e.message),
+    };
+    let bot = match bots::from_spec(args.get(1).map(String::as_str).unwrap_or(DEFAULT_BOT)) {
+        Ok(bot) => bot,
+        Err(e) => return println!("can't create bot: {}", e.message),
+    };
+    let rules = match args.get(2).map(|rules| RuleSet::parse(rules)).transpose() {
+        Ok(rules) => rules.unwrap_or_default(),
+        Err(e) => return println!("{}", e.message),
+    };
+
+    let mut output = io::stdout().lock();
+    let _ = render_field(&mut output, &position.field, None, &[]);
+    let field = match rules
+        .new_field(position.field.size)
+        .with_stones(&position.field.stones(), position.field.last_move())
+    {
+        Ok(field) => field,
+        Err(e) => return println!("{}", e.message),
+    };
+
+    let play_info = PlayInfo {
+        field_size: position.field.size,
+        win_condition: position.win_condition,
+        rules,
+        side: position.side,
+        player_id: None,
+        game_id: None,
+    };
+    let analysis = bot
+        .start_game(&play_info)
+        .and_then(|_| bot.analyze(&field, position.side, ANALYSIS_CANDIDATES));
+    match analysis {
+        Ok(Some(analysis)) => println!("{:?} to move, {analysis}", position.side),
+        Ok(None) => match bot.calculate_best_turn(&field, position.side) {
+            Ok(coords) => println!(
+                "best turn for {:?}: {}",
+                position.side,
+                coords.to_algebraic()
+            ),
+            Err(e) => println!("bot failed: {}", e.message),
+        },
+        Err(e) => println!("bot failed: {}", e.message),
+    }
 }
@@ -0,0 +1,29 @@
+use std::thread;
+
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::common::{error::ServerError, logging};
+
+use super::metrics;
+
+/// Serves [`metrics()`] on `GET /metrics` from a background thread.
+pub fn serve(addr: &str) -> Result<(), ServerError> {
+    let server = Server::http(addr).map_err(|e| ServerError::of_io(&e.to_string()))?;
+    logging::info!("metrics available at http://{addr}/metrics");
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match (request.method(), request.url()) {
+                (Method::Get, "/metrics") => {
+                    let content_type =
+                        Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap();
+                    Response::from_string(metrics().render()).with_header(content_type)
+                }
+                _ => Response::from_string("not found").with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    Ok(())
+}
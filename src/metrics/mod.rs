@@ -0,0 +1,85 @@
+//! Bot, game and HTTP client statistics in the Prometheus text format.
+//!
+//! Everything is collected into the process wide [`metrics()`] and served by
+//! [`exporter::serve`] on `GET /metrics`.
+
+pub mod exporter;
+pub mod observer;
+pub mod registry;
+
+use std::sync::OnceLock;
+
+use self::registry::{Counter, Histogram, DEFAULT_BUCKETS};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub struct Metrics {
+    pub games_played: Counter,
+    pub games_won: Counter,
+    pub games_drawn: Counter,
+    pub games_lost: Counter,
+    pub game_errors: Counter,
+    pub think_time: Histogram,
+    pub http_requests: Counter,
+    pub http_errors: Counter,
+    pub http_latency: Histogram,
+    pub http_retries: Counter,
+}
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            games_played: Counter::new("tictactoe_games_played_total", "Finished games."),
+            games_won: Counter::new("tictactoe_games_won_total", "Games won by the bot."),
+            games_drawn: Counter::new("tictactoe_games_drawn_total", "Games ended in a draw."),
+            games_lost: Counter::new("tictactoe_games_lost_total", "Games lost by the bot."),
+            game_errors: Counter::new("tictactoe_game_errors_total", "Games aborted by an error."),
+            think_time: Histogram::new(
+                "tictactoe_think_time_seconds",
+                "Time the bot spent choosing a move.",
+                &DEFAULT_BUCKETS,
+            ),
+            http_requests: Counter::new(
+                "tictactoe_http_requests_total",
+                "Requests to the game server by endpoint and status.",
+            ),
+            http_errors: Counter::new(
+                "tictactoe_http_errors_total",
+                "Failed requests to the game server by endpoint.",
+            ),
+            http_latency: Histogram::new(
+                "tictactoe_http_request_duration_seconds",
+                "Latency of requests to the game server, long polling included.",
+                &DEFAULT_BUCKETS,
+            ),
+            http_retries: Counter::new(
+                "tictactoe_http_retries_total",
+                "Retried requests to the game server by endpoint.",
+            ),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        for counter in [
+            &self.games_played,
+            &self.games_won,
+            &self.games_drawn,
+            &self.games_lost,
+        ] {
+            counter.render(&mut output);
+        }
+        self.game_errors.render(&mut output);
+        self.think_time.render(&mut output);
+        self.http_requests.render(&mut output);
+        self.http_errors.render(&mut output);
+        self.http_latency.render(&mut output);
+        self.http_retries.render(&mut output);
+
+        output
+    }
+}
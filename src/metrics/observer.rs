@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use crate::common::{
+    bot::{Analysis, Turn},
+    error::GameProcessError,
+    game_process::GameResult,
+    observer::GameObserver,
+};
+
+use super::metrics;
+
+/// Feeds game results and think times of the observed game into [`metrics()`].
+pub struct MetricsObserver;
+
+impl GameObserver for MetricsObserver {
+    fn on_bot_turn(&mut self, _turn: &Turn, think_time: Duration, _analysis: Option<&Analysis>) {
+        metrics().think_time.observe(&[], think_time.as_secs_f64());
+    }
+
+    fn on_error(&mut self, _error: &GameProcessError) {
+        metrics().game_errors.inc(&[]);
+    }
+
+    fn on_game_finished(&mut self, result: GameResult) {
+        let metrics = metrics();
+        metrics.games_played.inc(&[]);
+        match result {
+            GameResult::Win => metrics.games_won.inc(&[]),
+            GameResult::Draw => metrics.games_drawn.inc(&[]),
+            GameResult::Defeat => metrics.games_lost.inc(&[]),
+        }
+    }
+}
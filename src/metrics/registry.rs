@@ -0,0 +1,178 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+pub static DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Monotonic counter, one value per label set.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    values: Mutex<BTreeMap<String, u64>>,
+}
+
+/// Cumulative histogram, one set of buckets per label set.
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    buckets: &'static [f64],
+    series: Mutex<BTreeMap<String, Series>>,
+}
+
+struct Series {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Counter {
+    pub fn new(name: &'static str, help: &'static str) -> Self {
+        Counter {
+            name,
+            help,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inc(&self, labels: Labels) {
+        *self
+            .values
+            .lock()
+            .unwrap()
+            .entry(render_labels(labels))
+            .or_default() += 1;
+    }
+
+    pub fn render(&self, output: &mut String) {
+        let _ = writeln!(output, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(output, "# TYPE {} counter", self.name);
+        let values = self.values.lock().unwrap();
+        if values.is_empty() {
+            let _ = writeln!(output, "{} 0", self.name);
+        }
+        for (labels, value) in values.iter() {
+            let _ = writeln!(output, "{}{labels} {value}", self.name);
+        }
+    }
+}
+
+impl Histogram {
+    pub fn new(name: &'static str, help: &'static str, buckets: &'static [f64]) -> Self {
+        Histogram {
+            name,
+            help,
+            buckets,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe(&self, labels: Labels, value: f64) {
+        let mut series = self.series.lock().unwrap();
+        let series = series
+            .entry(render_labels(labels))
+            .or_insert_with(|| Series {
+                counts: vec![0; self.buckets.len()],
+                sum: 0.0,
+                count: 0,
+            });
+
+        for (bucket, count) in self.buckets.iter().zip(&mut series.counts) {
+            if value <= *bucket {
+                *count += 1;
+            }
+        }
+        series.sum += value;
+        series.count += 1;
+    }
+
+    pub fn render(&self, output: &mut String) {
+        let _ = writeln!(output, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(output, "# TYPE {} histogram", self.name);
+        for (labels, series) in self.series.lock().unwrap().iter() {
+            let with_le = |le: &str| match labels.is_empty() {
+                true => format!("{{le=\"{le}\"}}"),
+                false => format!("{},le=\"{le}\"}}", &labels[..labels.len() - 1]),
+            };
+            for (bucket, count) in self.buckets.iter().zip(&series.counts) {
+                let le = with_le(&bucket.to_string());
+                let _ = writeln!(output, "{}_bucket{le} {count}", self.name);
+            }
+            let _ = writeln!(
+                output,
+                "{}_bucket{} {}",
+                self.name,
+                with_le("+Inf"),
+                series.count
+            );
+            let _ = writeln!(output, "{}_sum{labels} {}", self.name, series.sum);
+            let _ = writeln!(output, "{}_count{labels} {}", self.name, series.count);
+        }
+    }
+}
+
+fn render_labels(labels: Labels) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_renders_label_sets() {
+        let counter = Counter::new("requests_total", "Requests.");
+        counter.inc(&[("endpoint", "a")]);
+        counter.inc(&[("endpoint", "a")]);
+        counter.inc(&[("endpoint", "b\"")]);
+
+        let mut output = String::new();
+        counter.render(&mut output);
+
+        assert_eq!(
+            output,
+            "# HELP requests_total Requests.\n\
+             # TYPE requests_total counter\n\
+             requests_total{endpoint=\"a\"} 2\n\
+             requests_total{endpoint=\"b\\\"\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn histogram_renders_cumulative_buckets() {
+        let histogram = Histogram::new("latency_seconds", "Latency.", &[0.1, 1.0]);
+        histogram.observe(&[("endpoint", "a")], 0.05);
+        histogram.observe(&[("endpoint", "a")], 0.5);
+        histogram.observe(&[("endpoint", "a")], 5.0);
+
+        let mut output = String::new();
+        histogram.render(&mut output);
+
+        assert_eq!(
+            output,
+            "# HELP latency_seconds Latency.\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{endpoint=\"a\",le=\"0.1\"} 1\n\
+             latency_seconds_bucket{endpoint=\"a\",le=\"1\"} 2\n\
+             latency_seconds_bucket{endpoint=\"a\",le=\"+Inf\"} 3\n\
+             latency_seconds_sum{endpoint=\"a\"} 5.55\n\
+             latency_seconds_count{endpoint=\"a\"} 3\n"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Line-based protocol for bots running as separate processes.
+//!
+//! The host writes one command per line to the engine's stdin and reads
+//! replies from its stdout. Coordinates are zero-based, sides are `X` or `O`.
+//!
+//! ```text
+//! host -> engine                          engine -> host
+//! PROTOCOL 1                              OK [engine name]
+//! START <width> <height> <win_condition> [rules]
+//!                                         OK
+//! POSITION <side to move> [<x>,<y>,<side> ...]
+//! GO <time limit in ms>                   MOVE <x> <y>
+//! QUIT
+//! ```
+//!
+//! `rules` is sent only for variants other than `standard`, see `common::rules`.
+//!
+//! Engines may print `INFO <text>` lines at any time, the host skips them.
+//! `ERROR <text>` may be sent instead of any expected reply.
+
+use std::time::Duration;
+
+use crate::common::{
+    bot::{Coords, Side, Size, Turn},
+    error::BotError,
+    rules::RuleSet,
+};
+
+pub static PROTOCOL_VERSION: u32 = 1;
+
+pub enum HostCommand {
+    Protocol(u32),
+    Start {
+        size: Size,
+        win_condition: usize,
+        rules: RuleSet,
+    },
+    Position {
+        side: Side,
+        stones: Vec<Turn>,
+    },
+    Go(Duration),
+    Quit,
+}
+
+#[derive(Debug)]
+pub enum EngineReply {
+    Ok(Option<String>),
+    Move(Coords),
+    Info(String),
+    Error(String),
+}
+
+impl HostCommand {
+    pub fn to_line(&self) -> String {
+        match self {
+            HostCommand::Protocol(version) => format!("PROTOCOL {version}"),
+            HostCommand::Start {
+                size,
+                win_condition,
+                rules,
+            } => {
+                let line = format!("START {} {} {}", size.width, size.height, win_condition);
+                match *rules == RuleSet::STANDARD {
+                    true => line,
+                    false => format!("{line} {rules}"),
+                }
+            }
+            HostCommand::Position { side, stones } => {
+                let mut line = format!("POSITION {side:?}");
+                for stone in stones {
+                    line += &format!(" {},{},{:?}", stone.coords.x, stone.coords.y, stone.side);
+                }
+                line
+            }
+            HostCommand::Go(time_limit) => format!("GO {}", time_limit.as_millis()),
+            HostCommand::Quit => "QUIT".to_owned(),
+        }
+    }
+}
+
+impl EngineReply {
+    pub fn parse(line: &str) -> Result<Self, BotError> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match keyword {
+            "OK" if rest.is_empty() => Ok(EngineReply::Ok(None)),
+            "OK" => Ok(EngineReply::Ok(Some(rest.to_owned()))),
+            "INFO" => Ok(EngineReply::Info(rest.to_owned())),
+            "ERROR" => Ok(EngineReply::Error(rest.to_owned())),
+            "MOVE" => {
+                let coords: Vec<isize> = rest
+                    .split_whitespace()
+                    .map(|value| value.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| BotError::of_protocol(line))?;
+
+                match coords[..] {
+                    [x, y] => Ok(EngineReply::Move(Coords { x, y })),
+                    _ => Err(BotError::of_protocol(line)),
+                }
+            }
+            _ => Err(BotError::of_protocol(line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_formatted() {
+        let size = Size {
+            width: 15,
+            height: 15,
+        };
+        let start = |rules| HostCommand::Start {
+            size,
+            win_condition: 5,
+            rules,
+        };
+        let position = HostCommand::Position {
+            side: Side::O,
+            stones: vec![Turn {
+                coords: Coords { x: 7, y: -1 },
+                side: Side::X,
+            }],
+        };
+
+        assert_eq!(
+            HostCommand::Protocol(PROTOCOL_VERSION).to_line(),
+            "PROTOCOL 1"
+        );
+        assert_eq!(start(RuleSet::STANDARD).to_line(), "START 15 15 5");
+        assert_eq!(
+            start(RuleSet::parse("misere").unwrap()).to_line(),
+            "START 15 15 5 misere"
+        );
+        assert_eq!(position.to_line(), "POSITION O 7,-1,X");
+        assert_eq!(HostCommand::Go(Duration::from_secs(2)).to_line(), "GO 2000");
+        assert_eq!(HostCommand::Quit.to_line(), "QUIT");
+    }
+
+    #[test]
+    fn replies_are_parsed() {
+        assert!(matches!(
+            EngineReply::parse("OK").unwrap(),
+            EngineReply::Ok(None)
+        ));
+        assert!(matches!(
+            EngineReply::parse("OK  pbrain 1.0 ").unwrap(),
+            EngineReply::Ok(Some(name)) if name == "pbrain 1.0"
+        ));
+        assert!(matches!(
+            EngineReply::parse("MOVE 3 -2\n").unwrap(),
+            EngineReply::Move(Coords { x: 3, y: -2 })
+        ));
+        assert!(matches!(
+            EngineReply::parse("INFO depth 4").unwrap(),
+            EngineReply::Info(text) if text == "depth 4"
+        ));
+        assert!(matches!(
+            EngineReply::parse("ERROR no moves").unwrap(),
+            EngineReply::Error(text) if text == "no moves"
+        ));
+    }
+
+    #[test]
+    fn wrong_replies_are_rejected() {
+        for line in [
+            "",
+            "MOVE",
+            "MOVE 3",
+            "MOVE 3 4 5",
+            "MOVE a b",
+            "BESTMOVE 3 4",
+            "ok",
+        ] {
+            assert!(EngineReply::parse(line).is_err(), "{line}");
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Gomocup engine protocol, lets any `Bot` run inside gomoku tournament managers.
+//!
+//! See <https://plastovicka.github.io/protocl2en.htm>. Only the mandatory part
+//! is supported: `START`, `RECTSTART`, `RESTART`, `BEGIN`, `TURN`, `BOARD`,
+//! `INFO`, `ABOUT` and `END`. Of `INFO` only `rule` is used, its bit 1 enables
+//! the `exact` rule (exactly five in a row wins) and bit 4 the `renju` one.
+//! Continuous games aren't, so `BOARD` rejects the winning line marker `3`.
+
+use std::io::{self, BufRead, Write};
+
+use crate::common::{
+    bot::{Bot, Coords, Field, Side, Size, Turn},
+    game_process::PlayInfo,
+    rules::RuleSet,
+};
+
+static ABOUT: &str = "name=\"tic-tac-toe-bot\", version=\"0.1.0\"";
+static DEFAULT_WIN_CONDITION: usize = 5;
+static RULE_EXACT_FIVE: u32 = 1;
+static RULE_RENJU: u32 = 4;
+
+pub struct GomocupEngine {
+    bot: Box<dyn Bot>,
+    field: Option<Field>,
+    side: Option<Side>,
+    rules: RuleSet,
+    win_condition: usize,
+}
+
+impl GomocupEngine {
+    pub fn new(bot: Box<dyn Bot>) -> Self {
+        GomocupEngine {
+            bot,
+            field: None,
+            side: None,
+            rules: RuleSet::STANDARD,
+            win_condition: DEFAULT_WIN_CONDITION,
+        }
+    }
+
+    /// Plays `win_condition` in a row instead of gomoku's five.
+    pub fn with_win_condition(mut self, win_condition: usize) -> Self {
+        self.win_condition = win_condition;
+        self
+    }
+
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        let mut lines = input.lines();
+
+        while let Some(line) = lines.next() {
+            let line = line?;
+            let (command, args) = split_command(&line);
+
+            let answer = match command.as_str() {
+                "" => None,
+                "INFO" => {
+                    self.info(args);
+                    None
+                }
+                "END" => return Ok(()),
+                "ABOUT" => Some(ABOUT.to_owned()),
+                "START" => Some(self.start(parse_size(args, ' '))),
+                "RECTSTART" => Some(self.start(parse_size(args, ','))),
+                "RESTART" => Some(self.restart()),
+                "BEGIN" => Some(self.begin()),
+                "TURN" => Some(self.turn(args)),
+                "BOARD" => {
+                    let mut stones = vec![];
+                    for line in lines.by_ref() {
+                        let line = line?;
+                        if line.trim().eq_ignore_ascii_case("DONE") {
+                            break;
+                        }
+                        stones.push(line);
+                    }
+                    Some(self.board(&stones))
+                }
+                _ => Some(format!("UNKNOWN {command}")),
+            };
+
+            if let Some(answer) = answer {
+                writeln!(output, "{answer}")?;
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start(&mut self, size: Option<Size>) -> String {
+        match size {
+            Some(size) if size.width >= self.win_condition && size.height >= self.win_condition => {
+                self.field = Some(Field::new(size));
+                self.side = None;
+                "OK".to_owned()
+            }
+            _ => "ERROR unsupported board size".to_owned(),
+        }
+    }
+
+    fn info(&mut self, args: &str) {
+        let (key, value) = args.split_once(' ').unwrap_or((args, ""));
+        if key.eq_ignore_ascii_case("rule") {
+            if let Ok(rule) = value.trim().parse::<u32>() {
+                self.rules.exact = rule & RULE_EXACT_FIVE != 0;
+                self.rules.renju = rule & RULE_RENJU != 0;
+            }
+        }
+    }
+
+    fn restart(&mut self) -> String {
+        match &self.field {
+            Some(field) => self.start(Some(field.size)),
+            None => "ERROR no game started".to_owned(),
+        }
+    }
+
+    fn begin(&mut self) -> String {
+        self.find_turn(Side::X)
+    }
+
+    fn turn(&mut self, args: &str) -> String {
+        let Some(coords) = parse_coords(args) else {
+            return format!("ERROR wrong coordinates {args}");
+        };
+        let side = self.side.unwrap_or(Side::O);
+        let Some(field) = self.field.as_mut() else {
+            return "ERROR no game started".to_owned();
+        };
+
+        let enemy_turn = Turn {
+            coords,
+            side: side.opposite(),
+        };
+        match self.rules.place(field, &enemy_turn, self.win_condition) {
+            Ok(_) => self.find_turn(side),
+            Err(e) => format!("ERROR {}", e.message),
+        }
+    }
+
+    fn board(&mut self, stones: &[String]) -> String {
+        let Some(field) = self.field.as_ref() else {
+            return "ERROR no game started".to_owned();
+        };
+        let mut field = Field::new(field.size);
+
+        let mut parsed = vec![];
+        for stone in stones {
+            let Some((coords, who)) = stone.rsplit_once(',') else {
+                return format!("ERROR wrong stone {stone}");
+            };
+            match (parse_coords(coords), who.trim()) {
+                (Some(coords), "1") => parsed.push((coords, true)),
+                (Some(coords), "2") => parsed.push((coords, false)),
+                (Some(_), "3") => return "ERROR continuous games are not supported".to_owned(),
+                _ => return format!("ERROR wrong stone {stone}"),
+            }
+        }
+
+        let own = parsed.iter().filter(|(_, own)| *own).count();
+        let side = if own * 2 == parsed.len() {
+            Side::X
+        } else {
+            Side::O
+        };
+
+        for (coords, own) in parsed {
+            let turn = Turn {
+                coords,
+                side: if own { side } else { side.opposite() },
+            };
+            if let Err(e) = self.rules.place(&mut field, &turn, self.win_condition) {
+                return format!("ERROR {}", e.message);
+            }
+        }
+
+        self.field = Some(field);
+        self.side = None;
+        self.find_turn(side)
+    }
+
+    fn find_turn(&mut self, side: Side) -> String {
+        let Some(field) = self.field.as_mut() else {
+            return "ERROR no game started".to_owned();
+        };
+
+        if self.side.is_none() {
+            let play_info = PlayInfo {
+                field_size: field.size,
+                win_condition: self.win_condition,
+                rules: self.rules,
+                side,
+                player_id: None,
+                game_id: None,
+            };
+            if let Err(e) = self.bot.start_game(&play_info) {
+                return format!("ERROR {}", e.message);
+            }
+            self.side = Some(side);
+        }
+
+        let turn = match self.bot.make_turn(field, side) {
+            Ok(turn) => turn,
+            Err(e) => return format!("ERROR {}", e.message),
+        };
+        match self.rules.place(field, &turn, self.win_condition) {
+            Ok(turn) => format!("{},{}", turn.coords.x, turn.coords.y),
+            Err(e) => format!("ERROR {}", e.message),
+        }
+    }
+}
+
+fn split_command(line: &str) -> (String, &str) {
+    let line = line.trim();
+    let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+    (command.to_ascii_uppercase(), args.trim())
+}
+
+fn parse_size(args: &str, separator: char) -> Option<Size> {
+    let mut values = args.split(separator).map(|value| value.trim().parse().ok());
+
+    match (values.next(), values.next()) {
+        (Some(Some(width)), Some(Some(height))) => Some(Size { width, height }),
+        (Some(Some(size)), None) => Some(Size {
+            width: size,
+            height: size,
+        }),
+        _ => None,
+    }
+}
+
+fn parse_coords(args: &str) -> Option<Coords> {
+    let (x, y) = args.split_once(',')?;
+
+    Some(Coords {
+        x: x.trim().parse().ok()?,
+        y: y.trim().parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::common::{bot::Cell, error::BotError};
+
+    use super::*;
+
+    /// Plays the first empty cell, row by row.
+    struct FirstEmptyBot;
+
+    impl Bot for FirstEmptyBot {
+        fn calculate_best_turn(&self, field: &Field, _side: Side) -> Result<Coords, BotError> {
+            (0..field.size.height as isize)
+                .flat_map(|y| (0..field.size.width as isize).map(move |x| Coords { x, y }))
+                .find(|&coords| matches!(field.get(coords), Some(Cell::Empty)))
+                .ok_or_else(BotError::of_no_moves)
+        }
+    }
+
+    fn run(engine: &mut GomocupEngine, input: &str) -> Vec<String> {
+        let mut output = vec![];
+        engine.run(Cursor::new(input), &mut output).unwrap();
+
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn plays_turns_after_start() {
+        let mut engine = GomocupEngine::new(Box::new(FirstEmptyBot));
+
+        let answers = run(
+            &mut engine,
+            "START 5\nBEGIN\nTURN 1,0\nTURN 9,9\nABOUT\nEND\nBEGIN\n",
+        );
+
+        assert_eq!(answers[..3], ["OK", "0,0", "2,0"]);
+        assert!(answers[3].starts_with("ERROR"));
+        assert_eq!(answers[4], ABOUT);
+        assert_eq!(answers.len(), 5);
+    }
+
+    #[test]
+    fn board_sets_up_position_for_side_to_move() {
+        let mut engine = GomocupEngine::new(Box::new(FirstEmptyBot));
+        let input = "START 10\nINFO rule 1\nBOARD\n0,0,1\n1,0,2\n2,0,2\nDONE\n";
+
+        let answers = run(&mut engine, input);
+
+        // one own stone against two, so the opponent moved first with X
+        assert_eq!(answers, ["OK", "3,0"]);
+        assert!(engine.rules.exact);
+        let field = engine.field.as_ref().unwrap();
+        assert!(matches!(
+            field.get(Coords { x: 0, y: 0 }),
+            Some(Cell::Value(Side::O))
+        ));
+        assert!(matches!(
+            field.get(Coords { x: 1, y: 0 }),
+            Some(Cell::Value(Side::X))
+        ));
+    }
+
+    #[test]
+    fn board_rejects_winning_line_marker() {
+        let mut engine = GomocupEngine::new(Box::new(FirstEmptyBot));
+
+        let answers = run(
+            &mut engine,
+            "START 10\nBOARD\n0,0,3\nDONE\nBOARD\n0,0\nDONE\n",
+        );
+
+        assert_eq!(answers[0], "OK");
+        assert_eq!(answers[1], "ERROR continuous games are not supported");
+        assert!(answers[2].starts_with("ERROR wrong stone"));
+    }
+
+    #[test]
+    fn board_rejects_forbidden_stone_with_renju() {
+        let mut engine = GomocupEngine::new(Box::new(FirstEmptyBot));
+        let o_stones = "0,0,1\n2,0,1\n4,0,1\n6,0,1\n8,0,1\n";
+        // the last X stone makes six in a row
+        let x_stones = "0,5,2\n1,5,2\n2,5,2\n4,5,2\n5,5,2\n3,5,2\n";
+        let input = format!("START 10\nINFO rule 4\nBOARD\n{o_stones}{x_stones}DONE\n");
+
+        let answers = run(&mut engine, &input);
+
+        assert_eq!(answers[0], "OK");
+        assert!(answers[1].starts_with("ERROR Move"), "{}", answers[1]);
+    }
+
+    #[test]
+    fn board_size_must_fit_win_condition() {
+        let mut engine = GomocupEngine::new(Box::new(FirstEmptyBot)).with_win_condition(3);
+
+        let answers = run(&mut engine, "START 4\nRECTSTART 2,9\nSTART x\nFOO\n");
+
+        assert_eq!(answers[0], "OK");
+        assert!(answers[1].starts_with("ERROR"));
+        assert!(answers[2].starts_with("ERROR"));
+        assert_eq!(answers[3], "UNKNOWN FOO");
+    }
+}
@@ -0,0 +1,345 @@
+//! Records of finished games.
+//!
+//! Every record is stored twice: as JSON and as a one-line text notation.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "width": 3,
+//!   "height": 3,
+//!   "win_condition": 3,
+//!   "rules": "standard",
+//!   "player_id": "e97b688c990afbbe",
+//!   "side": "X",
+//!   "started_at": 1700000000000,
+//!   "finished_at": 1700000004200,
+//!   "result": "X_WIN",
+//!   "moves": [{ "side": "X", "x": 1, "y": 1, "think_time_ms": 12 }]
+//! }
+//! ```
+//!
+//! `side` is the side our bot played, timestamps are unix milliseconds.
+//! Think time of opponent moves is the time we spent waiting for them.
+//!
+//! The text notation keeps the field, the moves in order and the result:
+//! `3x3w3 a1 b1 a2 b2 a3 X_WIN`, coordinates are algebraic as in `common::notation`.
+//! Variant rules follow the settings after a colon: `3x3w3:misere ...`.
+//! Older records have zero-based `x,y` coordinates, `3x3w3 0,0 1,0 ...`, which
+//! still load as the two notations can't be mistaken for one another.
+
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    bot::{Coords, Side, Size, Turn},
+    error::CoreError,
+    game_process::{GameResult, PlayInfo},
+    rules::RuleSet,
+};
+
+pub static RECORD_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GameRecord {
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    pub win_condition: usize,
+    #[serde(default)]
+    pub rules: RuleSet,
+    pub player_id: Option<String>,
+    pub side: RecordSide,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub result: RecordResult,
+    pub moves: Vec<MoveRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordSide {
+    X,
+    O,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordResult {
+    #[serde(rename = "X_WIN")]
+    XWin,
+    #[serde(rename = "O_WIN")]
+    OWin,
+    #[serde(rename = "DRAW")]
+    Draw,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct MoveRecord {
+    pub side: RecordSide,
+    pub x: isize,
+    pub y: isize,
+    pub think_time_ms: u64,
+}
+
+impl GameRecord {
+    pub fn new(
+        play_info: &PlayInfo,
+        started_at: SystemTime,
+        history: &[Turn],
+        think_times: &[Duration],
+        result: GameResult,
+    ) -> Self {
+        let side = play_info.side;
+        let winner = match result {
+            GameResult::Win => Some(side),
+            GameResult::Defeat => Some(side.opposite()),
+            GameResult::Draw => None,
+        };
+
+        GameRecord {
+            version: RECORD_VERSION,
+            width: play_info.field_size.width,
+            height: play_info.field_size.height,
+            win_condition: play_info.win_condition,
+            rules: play_info.rules,
+            player_id: play_info.player_id.clone(),
+            side: side.into(),
+            started_at: unix_millis(started_at),
+            finished_at: unix_millis(SystemTime::now()),
+            result: match winner {
+                Some(Side::X) => RecordResult::XWin,
+                Some(Side::O) => RecordResult::OWin,
+                None => RecordResult::Draw,
+            },
+            moves: history
+                .iter()
+                .zip(think_times)
+                .map(|(turn, think_time)| MoveRecord {
+                    side: turn.side.into(),
+                    x: turn.coords.x,
+                    y: turn.coords.y,
+                    think_time_ms: think_time.as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let record = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt") => GameRecord::from_notation(record.trim())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.message)),
+            _ => Ok(serde_json::from_str(&record)?),
+        }
+    }
+
+    pub fn field_size(&self) -> Size {
+        Size {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn turns(&self) -> Vec<Turn> {
+        self.moves
+            .iter()
+            .map(|m| Turn {
+                coords: Coords { x: m.x, y: m.y },
+                side: m.side.into(),
+            })
+            .collect()
+    }
+
+    pub fn to_notation(&self) -> String {
+        let mut notation = format!("{}x{}w{}", self.width, self.height, self.win_condition);
+        if self.rules != RuleSet::STANDARD {
+            notation += &format!(":{}", self.rules);
+        }
+        for m in &self.moves {
+            notation += " ";
+            notation += &Coords { x: m.x, y: m.y }.to_algebraic();
+        }
+        notation += &format!(" {}", self.result);
+
+        notation
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Self, CoreError> {
+        let wrong = || CoreError::of_notation(notation);
+        let mut parts = notation.split_whitespace();
+
+        let settings = parts.next().ok_or_else(wrong)?;
+        let (settings, rules) = settings.split_once(':').unwrap_or((settings, "standard"));
+        let (width, rest) = settings.split_once('x').ok_or_else(wrong)?;
+        let (height, win_condition) = rest.split_once('w').ok_or_else(wrong)?;
+
+        let mut parts: Vec<&str> = parts.collect();
+        let result = parts
+            .pop()
+            .and_then(RecordResult::parse)
+            .ok_or_else(wrong)?;
+
+        let mut side = Side::X;
+        let mut moves = vec![];
+        for algebraic in parts {
+            let coords = Coords::from_algebraic(algebraic)?;
+            moves.push(MoveRecord {
+                side: side.into(),
+                x: coords.x,
+                y: coords.y,
+                think_time_ms: 0,
+            });
+            side = side.opposite();
+        }
+
+        Ok(GameRecord {
+            version: RECORD_VERSION,
+            width: width.parse().map_err(|_| wrong())?,
+            height: height.parse().map_err(|_| wrong())?,
+            win_condition: win_condition.parse().map_err(|_| wrong())?,
+            rules: RuleSet::parse(rules)?,
+            player_id: None,
+            side: RecordSide::X,
+            started_at: 0,
+            finished_at: 0,
+            result,
+            moves,
+        })
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let name = format!(
+            "{}-{}",
+            self.started_at,
+            self.player_id.as_deref().unwrap_or("local")
+        );
+        let path = dir.join(format!("{name}.json"));
+
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        fs::write(dir.join(format!("{name}.txt")), self.to_notation() + "\n")?;
+        Ok(path)
+    }
+}
+
+impl RecordResult {
+    fn parse(name: &str) -> Option<Self> {
+        [RecordResult::XWin, RecordResult::OWin, RecordResult::Draw]
+            .into_iter()
+            .find(|result| result.to_string() == name)
+    }
+}
+
+/// The same names as in JSON records.
+impl Display for RecordResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RecordResult::XWin => "X_WIN",
+            RecordResult::OWin => "O_WIN",
+            RecordResult::Draw => "DRAW",
+        };
+        f.write_str(name)
+    }
+}
+
+impl From<Side> for RecordSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::X => RecordSide::X,
+            Side::O => RecordSide::O,
+        }
+    }
+}
+
+impl From<RecordSide> for Side {
+    fn from(side: RecordSide) -> Self {
+        match side {
+            RecordSide::X => Side::X,
+            RecordSide::O => Side::O,
+        }
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(result: GameResult) -> GameRecord {
+        let play_info = PlayInfo {
+            field_size: Size {
+                width: 3,
+                height: 3,
+            },
+            win_condition: 3,
+            rules: RuleSet::parse("misere").unwrap(),
+            side: Side::O,
+            player_id: Some("player-1".to_owned()),
+            game_id: None,
+        };
+        let history =
+            [(0, 0, Side::X), (1, 1, Side::O), (2, 0, Side::X)].map(|(x, y, side)| Turn {
+                coords: Coords { x, y },
+                side,
+            });
+        let think_times = [5, 10, 15].map(Duration::from_millis);
+
+        GameRecord::new(&play_info, UNIX_EPOCH, &history, &think_times, result)
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let record = record(GameResult::Draw);
+
+        let json = serde_json::to_string(&record).unwrap();
+        let loaded: GameRecord = serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains(r#""result":"DRAW""#));
+        assert_eq!(loaded.result, RecordResult::Draw);
+        assert_eq!(loaded.side, RecordSide::O);
+        assert_eq!(loaded.player_id.as_deref(), Some("player-1"));
+        assert_eq!(loaded.rules, record.rules);
+        assert_eq!(loaded.turns(), record.turns());
+        let think_times: Vec<u64> = loaded.moves.iter().map(|m| m.think_time_ms).collect();
+        assert_eq!(think_times, [5, 10, 15]);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let record = record(GameResult::Defeat);
+
+        let notation = record.to_notation();
+        let loaded = GameRecord::from_notation(&notation).unwrap();
+
+        assert_eq!(notation, "3x3w3:misere a1 b2 c1 X_WIN");
+        assert_eq!(loaded.result, RecordResult::XWin);
+        assert_eq!(loaded.turns(), record.turns());
+        assert_eq!(loaded.to_notation(), notation);
+        assert_eq!(record.result.to_string(), "X_WIN");
+    }
+
+    #[test]
+    fn loads_text_records_with_numeric_coords() {
+        let loaded = GameRecord::from_notation("3x3w3 0,0 1,1 2,0 X_WIN").unwrap();
+
+        assert_eq!(loaded.turns(), record(GameResult::Defeat).turns());
+        assert_eq!(loaded.to_notation(), "3x3w3 a1 b2 c1 X_WIN");
+    }
+
+    #[test]
+    fn wrong_text_records_are_rejected() {
+        for notation in ["", "3x3w3", "3x3w3 a1 Draw", "3x3 a1 DRAW", "3x3w3 a0 DRAW"] {
+            assert!(GameRecord::from_notation(notation).is_err(), "{notation}");
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod game_record;
+pub mod recorder;
+pub mod snapshot;
@@ -0,0 +1,122 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::common::{
+    bot::{Analysis, Side, Turn},
+    game_process::{GameResult, PlayInfo},
+    logging,
+    observer::GameObserver,
+};
+
+use super::game_record::GameRecord;
+
+/// Saves a `GameRecord` into `records_dir` when the observed game finishes.
+pub struct GameRecorder {
+    records_dir: PathBuf,
+    play_info: Option<PlayInfo>,
+    started_at: SystemTime,
+    history: Vec<Turn>,
+    think_times: Vec<Duration>,
+}
+
+impl GameRecorder {
+    pub fn new(records_dir: PathBuf) -> Self {
+        GameRecorder {
+            records_dir,
+            play_info: None,
+            started_at: SystemTime::now(),
+            history: vec![],
+            think_times: vec![],
+        }
+    }
+}
+
+impl GameObserver for GameRecorder {
+    fn on_game_started(&mut self, play_info: &PlayInfo) {
+        self.play_info = Some(play_info.clone());
+        self.started_at = SystemTime::now();
+        self.history.clear();
+        self.think_times.clear();
+    }
+
+    fn on_game_resumed(&mut self, play_info: &PlayInfo, history: &[Turn]) {
+        self.on_game_started(play_info);
+        self.history = history.to_vec();
+        self.think_times = vec![Duration::ZERO; history.len()];
+    }
+
+    fn on_side_chosen(&mut self, side: Side) {
+        if let Some(play_info) = &mut self.play_info {
+            play_info.side = side;
+        }
+    }
+
+    fn on_opponent_turn(&mut self, turn: &Turn, waited: Duration) {
+        self.history.push(*turn);
+        self.think_times.push(waited);
+    }
+
+    fn on_bot_turn(&mut self, turn: &Turn, think_time: Duration, _analysis: Option<&Analysis>) {
+        self.history.push(*turn);
+        self.think_times.push(think_time);
+    }
+
+    fn on_game_finished(&mut self, result: GameResult) {
+        let Some(play_info) = &self.play_info else {
+            return;
+        };
+
+        let record = GameRecord::new(
+            play_info,
+            self.started_at,
+            &self.history,
+            &self.think_times,
+            result,
+        );
+        match record.save(&self.records_dir) {
+            Ok(path) => logging::info!("game record saved: {}", path.display()),
+            Err(e) => logging::error!("can't save game record: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::common::{
+        bot::{Coords, Size},
+        rules::RuleSet,
+    };
+
+    use super::*;
+
+    #[test]
+    fn next_game_starts_with_empty_history() {
+        let play_info = PlayInfo {
+            field_size: Size {
+                width: 3,
+                height: 3,
+            },
+            win_condition: 3,
+            rules: RuleSet::STANDARD,
+            side: Side::X,
+            player_id: None,
+            game_id: None,
+        };
+        let turn = Turn {
+            coords: Coords { x: 1, y: 1 },
+            side: Side::X,
+        };
+        let mut recorder = GameRecorder::new(env::temp_dir());
+
+        recorder.on_game_started(&play_info);
+        recorder.on_bot_turn(&turn, Duration::from_millis(5), None);
+        recorder.on_game_started(&play_info);
+
+        assert!(recorder.history.is_empty());
+        assert!(recorder.think_times.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+//! Snapshots of games in progress, used to resume after a crash.
+//!
+//! `GameProcess` rewrites `<player_id>.json` in its snapshots directory after
+//! every step and removes it once the game is finished:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "player_id": "e97b688c990afbbe",
+//!   "game_id": "0",
+//!   "side": "X",
+//!   "win_condition": 3,
+//!   "rules": "standard",
+//!   "field": "3x3 3 O 3/1X1/3",
+//!   "moves": [{ "side": "X", "x": 1, "y": 1 }],
+//!   "state": "TURN_WAITING",
+//!   "bot_prediction": null
+//! }
+//! ```
+//!
+//! `field` is in the position notation of `common::notation`. Infinite fields
+//! are shown by the rectangle around their stones and rebuilt from `moves`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    bot::{Coords, Field, Side, Topology, Turn},
+    error::CoreError,
+    game_process::{GameState, PlayInfo},
+    rules::RuleSet,
+};
+
+use super::game_record::RecordSide;
+
+pub static SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GameSnapshot {
+    pub version: u32,
+    pub player_id: String,
+    pub game_id: Option<String>,
+    pub side: RecordSide,
+    pub win_condition: usize,
+    #[serde(default)]
+    pub rules: RuleSet,
+    pub field: String,
+    pub moves: Vec<SnapshotMove>,
+    pub state: SnapshotState,
+    pub bot_prediction: Option<SnapshotMove>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotMove {
+    pub side: RecordSide,
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotState {
+    #[serde(rename = "TURN_WAITING")]
+    TurnWaiting,
+    #[serde(rename = "BOT_THINKING")]
+    BotThinking,
+    #[serde(rename = "TURN_MAKING")]
+    TurnMaking,
+}
+
+impl GameSnapshot {
+    /// Returns `None` when there is nothing to resume: the game hasn't
+    /// started or is already finished, or the player isn't known to a server.
+    pub fn new(
+        play_info: &PlayInfo,
+        field: &Field,
+        history: &[Turn],
+        state: GameState,
+        bot_prediction: Option<Turn>,
+    ) -> Option<Self> {
+        let state = match state {
+            GameState::TurnWaiting => SnapshotState::TurnWaiting,
+            GameState::BotThinking => SnapshotState::BotThinking,
+            GameState::TurnMaking => SnapshotState::TurnMaking,
+            GameState::Initial
+            | GameState::Resuming
+            | GameState::OpeningProposing
+            | GameState::OpeningWaiting
+            | GameState::SideChoosing
+            | GameState::Finished(_) => return None,
+        };
+        let next_side = match history.last() {
+            Some(turn) => turn.side.opposite(),
+            None => Side::X,
+        };
+
+        Some(GameSnapshot {
+            version: SNAPSHOT_VERSION,
+            player_id: play_info.player_id.clone()?,
+            game_id: play_info.game_id.clone(),
+            side: play_info.side.into(),
+            win_condition: play_info.win_condition,
+            rules: play_info.rules,
+            field: match field.is_infinite() {
+                true => field
+                    .window(0)
+                    .0
+                    .to_notation(play_info.win_condition, next_side),
+                false => field.to_notation(play_info.win_condition, next_side),
+            },
+            moves: history.iter().map(|&turn| turn.into()).collect(),
+            state,
+            bot_prediction: bot_prediction.map(SnapshotMove::from),
+        })
+    }
+
+    pub fn path(dir: &Path, player_id: &str) -> PathBuf {
+        dir.join(format!("{player_id}.json"))
+    }
+
+    /// Snapshot files in `dir`, oldest first.
+    pub fn find_all(dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+
+        let mut paths: Vec<(PathBuf, _)> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                (path, modified)
+            })
+            .collect();
+        paths.sort_by_key(|(_, modified)| *modified);
+
+        paths.into_iter().map(|(path, _)| path).collect()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the snapshot through a temporary file, so a crash while
+    /// saving never leaves a broken one behind.
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = GameSnapshot::path(dir, &self.player_id);
+        let tmp = path.with_extension("json.tmp");
+
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    pub fn play_info(&self) -> Result<PlayInfo, CoreError> {
+        Ok(PlayInfo {
+            field_size: self.field()?.size,
+            win_condition: self.win_condition,
+            rules: self.rules,
+            side: self.side.into(),
+            player_id: Some(self.player_id.clone()),
+            game_id: self.game_id.clone(),
+        })
+    }
+
+    pub fn field(&self) -> Result<Field, CoreError> {
+        let turns = self.turns();
+        let last_move = turns.last().map(|turn| turn.coords);
+        if self.rules.topology == Topology::Infinite {
+            return Field::infinite().with_stones(&turns, last_move);
+        }
+
+        let field = Field::from_notation(&self.field)?.field;
+        match field.last_move() {
+            Some(_) => Ok(field),
+            // older snapshots leave the latest turn out of the notation
+            None => Field::new(field.size).with_stones(&field.stones(), last_move),
+        }
+    }
+
+    pub fn turns(&self) -> Vec<Turn> {
+        self.moves.iter().map(|&m| m.into()).collect()
+    }
+
+    pub fn bot_prediction(&self) -> Option<Turn> {
+        self.bot_prediction.map(Turn::from)
+    }
+}
+
+impl From<Turn> for SnapshotMove {
+    fn from(turn: Turn) -> Self {
+        SnapshotMove {
+            side: turn.side.into(),
+            x: turn.coords.x,
+            y: turn.coords.y,
+        }
+    }
+}
+
+impl From<SnapshotMove> for Turn {
+    fn from(m: SnapshotMove) -> Self {
+        Turn {
+            coords: Coords { x: m.x, y: m.y },
+            side: m.side.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::bot::Size;
+
+    use super::*;
+
+    #[test]
+    fn infinite_field_round_trip() {
+        let rules = RuleSet::parse("infinite").unwrap();
+        let play_info = PlayInfo {
+            field_size: Size {
+                width: 0,
+                height: 0,
+            },
+            win_condition: 5,
+            rules,
+            side: Side::O,
+            player_id: Some("p1".to_owned()),
+            game_id: None,
+        };
+        let history =
+            [(0, 0, Side::X), (-1, 1, Side::O), (1, -2, Side::X)].map(|(x, y, side)| Turn {
+                coords: Coords { x, y },
+                side,
+            });
+        let mut field = rules.new_field(play_info.field_size);
+        for turn in &history {
+            rules.place(&mut field, turn, 5).unwrap();
+        }
+
+        let snapshot =
+            GameSnapshot::new(&play_info, &field, &history, GameState::TurnWaiting, None).unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let loaded: GameSnapshot = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.field, "3x4 5 O 2X/3/1X1/O2 c1");
+        let resumed = loaded.field().unwrap();
+        assert!(resumed.is_infinite());
+        assert_eq!(resumed.stones(), field.stones());
+        assert_eq!(resumed.last_move(), Some(Coords { x: 1, y: -2 }));
+        assert_eq!(loaded.play_info().unwrap().field_size.width, 0);
+    }
+}
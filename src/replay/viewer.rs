@@ -0,0 +1,285 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    common::{
+        bot::{Bot, Cell, Coords, Field, Side, Turn},
+        error::BotError,
+        game_process::PlayInfo,
+        notation,
+    },
+    record::game_record::GameRecord,
+};
+
+static HELP: &str = "commands: [n]ext, [p]rev, [s]tart, [e]nd, <move number>, [q]uit";
+static LAST_MOVE_STYLE: &str = "\x1b[7m";
+static WINNING_LINE_STYLE: &str = "\x1b[1;32m";
+static RESET_STYLE: &str = "\x1b[0m";
+
+pub struct ReplayViewer {
+    record: GameRecord,
+    turns: Vec<Turn>,
+    position: usize,
+    bot: Option<Box<dyn Bot>>,
+}
+
+impl ReplayViewer {
+    /// Starts the `bot` suggesting moves, if any, for the game of the record.
+    pub fn new(record: GameRecord, bot: Option<Box<dyn Bot>>) -> Result<Self, BotError> {
+        if let Some(bot) = &bot {
+            bot.start_game(&PlayInfo {
+                field_size: record.field_size(),
+                win_condition: record.win_condition,
+                rules: record.rules,
+                side: record.side.into(),
+                player_id: None,
+                game_id: None,
+            })?;
+        }
+
+        Ok(ReplayViewer {
+            turns: record.turns(),
+            position: record.moves.len(),
+            record,
+            bot,
+        })
+    }
+
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        writeln!(output, "{}", self.record.to_notation())?;
+        writeln!(output, "{HELP}")?;
+        self.render(&mut output)?;
+
+        for line in input.lines() {
+            let line = line?;
+            let last = self.turns.len();
+
+            self.position = match line.trim() {
+                "" | "n" => (self.position + 1).min(last),
+                "p" => self.position.saturating_sub(1),
+                "s" => 0,
+                "e" => last,
+                "q" => return Ok(()),
+                command => match command.parse::<usize>() {
+                    Ok(position) => position.min(last),
+                    Err(_) => {
+                        writeln!(output, "{HELP}")?;
+                        continue;
+                    }
+                },
+            };
+
+            self.render(&mut output)?;
+        }
+
+        Ok(())
+    }
+
+    fn render(&self, output: &mut impl Write) -> io::Result<()> {
+        let field = match self.field_at(self.position) {
+            Ok(field) => field,
+            Err(message) => return writeln!(output, "broken record: {message}"),
+        };
+        let last_turn = self.position.checked_sub(1).map(|i| self.turns[i]);
+        let (rules, win_condition) = (self.record.rules, self.record.win_condition);
+        let winning_line = last_turn
+            .and_then(|turn| rules.winning_line(&field, turn.side, win_condition))
+            .unwrap_or_default();
+
+        writeln!(output)?;
+        render_field(
+            output,
+            &field,
+            last_turn.map(|turn| turn.coords),
+            &winning_line,
+        )?;
+
+        match last_turn {
+            Some(turn) => {
+                let think_time = self.record.moves[self.position - 1].think_time_ms;
+                writeln!(
+                    output,
+                    "move {}/{}: {:?} {} ({think_time} ms)",
+                    self.position,
+                    self.turns.len(),
+                    turn.side,
+                    turn.coords.to_algebraic()
+                )?;
+            }
+            None => writeln!(output, "move 0/{}", self.turns.len())?,
+        }
+
+        if self.position == self.turns.len() {
+            writeln!(output, "result: {}", self.record.result)?;
+        } else if !field.is_infinite() {
+            let side = self.turns[self.position].side;
+            writeln!(
+                output,
+                "position: {}",
+                field.to_notation(self.record.win_condition, side)
+            )?;
+        }
+
+        if let (Some(bot), Some(next_turn)) = (&self.bot, self.turns.get(self.position)) {
+            match bot.calculate_best_turn(&field, next_turn.side) {
+                Ok(coords) => writeln!(output, "bot suggests: {}", coords.to_algebraic())?,
+                Err(e) => writeln!(output, "bot failed: {}", e.message)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn field_at(&self, position: usize) -> Result<Field, String> {
+        let mut field = self.record.rules.new_field(self.record.field_size());
+        for turn in &self.turns[..position] {
+            field.add_turn(turn).map_err(|e| e.message)?;
+        }
+
+        Ok(field)
+    }
+}
+
+pub fn render_field(
+    output: &mut impl Write,
+    field: &Field,
+    last_move: Option<Coords>,
+    highlighted: &[Coords],
+) -> io::Result<()> {
+    // infinite fields are drawn around their stones, numbered like `x,y` cells
+    let (size, origin) = match field.is_infinite() {
+        true => {
+            let (window, origin) = field.window(1);
+            (window.size, origin)
+        }
+        false => (field.size, Coords { x: 0, y: 0 }),
+    };
+
+    write!(output, "    ")?;
+    for x in 0..size.width {
+        match field.is_infinite() {
+            true => write!(output, "{:>3}", origin.x + x as isize)?,
+            false => write!(output, "{:>3}", notation::column_name(x))?,
+        }
+    }
+    writeln!(output)?;
+
+    for y in (0..size.height).rev() {
+        match field.is_infinite() {
+            true => write!(output, "{:>4}", origin.y + y as isize)?,
+            false => write!(output, "{:>4}", y + 1)?,
+        }
+        for x in 0..size.width {
+            let coords = Coords {
+                x: origin.x + x as isize,
+                y: origin.y + y as isize,
+            };
+            let symbol = match field.get(coords) {
+                Some(Cell::Value(Side::X)) => "X",
+                Some(Cell::Value(Side::O)) => "O",
+                _ => ".",
+            };
+            let style = if last_move == Some(coords) {
+                LAST_MOVE_STYLE
+            } else if highlighted.contains(&coords) {
+                WINNING_LINE_STYLE
+            } else {
+                ""
+            };
+
+            match style {
+                "" => write!(output, "  {symbol}")?,
+                style => write!(output, "  {style}{symbol}{RESET_STYLE}")?,
+            }
+        }
+        writeln!(output)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    /// Suggests a1 and counts the games it was started for.
+    struct CornerBot(Arc<AtomicUsize>);
+
+    impl Bot for CornerBot {
+        fn start_game(&self, _play_info: &PlayInfo) -> Result<(), BotError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn calculate_best_turn(&self, _field: &Field, _side: Side) -> Result<Coords, BotError> {
+            Ok(Coords { x: 0, y: 0 })
+        }
+    }
+
+    fn replay(commands: &str, bot: Option<Box<dyn Bot>>) -> String {
+        replay_record("3x3w3 a1 b1 a2 b2 a3 X_WIN", commands, bot)
+    }
+
+    fn replay_record(notation: &str, commands: &str, bot: Option<Box<dyn Bot>>) -> String {
+        let record = GameRecord::from_notation(notation).unwrap();
+        let mut viewer = ReplayViewer::new(record, bot).unwrap();
+        let mut output = vec![];
+        viewer.run(commands.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn navigates_through_moves() {
+        let output = replay("s\nn\n\np\n4\n9\nwat\ne\nq\nn\n", None);
+        let moves: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("move "))
+            .map(|line| line.split(':').next().unwrap())
+            .collect();
+
+        let expected = [5, 0, 1, 2, 1, 4, 5, 5].map(|position| format!("move {position}/5"));
+        assert_eq!(moves, expected);
+        assert_eq!(output.matches(HELP).count(), 2);
+        assert!(output.contains("position: 3x3 3 X XO1/3/3"));
+        assert!(output.ends_with("result: X_WIN\n"));
+    }
+
+    #[test]
+    fn renders_last_move_and_winning_line() {
+        let output = replay("q\n", None);
+
+        assert!(output.contains(&format!("   3  {LAST_MOVE_STYLE}X{RESET_STYLE}  .  .")));
+        assert!(output.contains(&format!("   1  {WINNING_LINE_STYLE}X{RESET_STYLE}  O  .")));
+        assert!(output.contains("move 5/5: X a3 (0 ms)"));
+    }
+
+    #[test]
+    fn renders_infinite_field_around_stones() {
+        let output = replay_record("0x0w3:infinite a1 -1,0 -1,-1 b1 1,1 X_WIN", "q\n", None);
+
+        assert!(output.contains("     -2 -1  0  1  2\n"));
+        assert!(output.contains(&format!(
+            "   0  .  O  {WINNING_LINE_STYLE}X{RESET_STYLE}  O  ."
+        )));
+        assert!(output.contains(&format!(
+            "  -1  .  {WINNING_LINE_STYLE}X{RESET_STYLE}  .  .  ."
+        )));
+        assert!(output.contains("result: X_WIN"));
+    }
+
+    #[test]
+    fn starts_bot_once() {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let bot = CornerBot(starts.clone());
+
+        let output = replay("s\nn\nn\nq\n", Some(Box::new(bot)));
+
+        assert_eq!(starts.load(Ordering::Relaxed), 1);
+        assert_eq!(output.matches("bot suggests: a1").count(), 3);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    api_client::dto::{CoordsDto, OpeningDto, RegisterInfoDto, SideChoiceDto},
+    common::{error::ServerError, logging, rules::GameSettings},
+};
+
+use super::lobby::Lobby;
+
+struct Shared {
+    lobby: Mutex<Lobby>,
+    updated: Condvar,
+}
+
+struct LongPolling {
+    enabled: bool,
+    timeout: Duration,
+}
+
+pub fn run(addr: &str, settings: GameSettings) -> Result<(), ServerError> {
+    let server = Server::http(addr).map_err(|e| ServerError::of_io(&e.to_string()))?;
+    let shared = Arc::new(Shared {
+        lobby: Mutex::new(Lobby::new(settings)),
+        updated: Condvar::new(),
+    });
+    logging::info!("server listening on {addr}");
+
+    for request in server.incoming_requests() {
+        let shared = shared.clone();
+        thread::spawn(move || handle(&shared, request));
+    }
+
+    Ok(())
+}
+
+fn handle(shared: &Shared, mut request: Request) {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let polling = parse_long_polling(query);
+
+    let result = match (request.method(), &segments[..]) {
+        (Method::Post, ["api", "v1", "matchmaking", "queue"]) => {
+            read_body::<RegisterInfoDto>(&mut request).map(|info| {
+                let player = shared.lobby.lock().unwrap().register(&info.name);
+                shared.updated.notify_all();
+                to_json(&player)
+            })
+        }
+        (Method::Get, ["api", "v1", "players", id]) => {
+            let lobby = wait_until(shared, &polling, |lobby| lobby.is_in_game(id));
+            lobby.player(id).map(|player| to_json(&player))
+        }
+        (Method::Get, ["api", "v1", "players", id, "game"]) => {
+            let lobby = wait_until(shared, &polling, |lobby| lobby.is_turn_ready(id));
+            lobby.game(id).map(|game| to_json(&game))
+        }
+        (Method::Post, ["api", "v1", "players", id, "game", "turn"]) => {
+            read_body::<CoordsDto>(&mut request).and_then(|coords| {
+                let game = shared.lobby.lock().unwrap().make_turn(id, coords)?;
+                shared.updated.notify_all();
+                Ok(to_json(&game))
+            })
+        }
+        (Method::Post, ["api", "v1", "players", id, "game", "opening"]) => {
+            read_body::<OpeningDto>(&mut request).and_then(|opening| {
+                let game = shared.lobby.lock().unwrap().propose_opening(id, opening)?;
+                shared.updated.notify_all();
+                Ok(to_json(&game))
+            })
+        }
+        (Method::Post, ["api", "v1", "players", id, "game", "side"]) => {
+            read_body::<SideChoiceDto>(&mut request).and_then(|choice| {
+                let game = shared.lobby.lock().unwrap().choose_side(id, choice)?;
+                shared.updated.notify_all();
+                Ok(to_json(&game))
+            })
+        }
+        _ => Err(ServerError::of_not_found(path)),
+    };
+
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(e) => {
+            let method = request.method();
+            logging::warning!("{method} {path} failed with {}: {}", e.status, e.message);
+            (
+                e.status,
+                serde_json::json!({ "message": e.message }).to_string(),
+            )
+        }
+    };
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+
+    let _ = request.respond(response);
+}
+
+fn wait_until<'a>(
+    shared: &'a Shared,
+    polling: &LongPolling,
+    ready: impl Fn(&Lobby) -> bool,
+) -> std::sync::MutexGuard<'a, Lobby> {
+    let lobby = shared.lobby.lock().unwrap();
+    if !polling.enabled {
+        return lobby;
+    }
+
+    shared
+        .updated
+        .wait_timeout_while(lobby, polling.timeout, |lobby| !ready(lobby))
+        .unwrap()
+        .0
+}
+
+fn parse_long_polling(query: &str) -> LongPolling {
+    let mut polling = LongPolling {
+        enabled: false,
+        timeout: Duration::ZERO,
+    };
+
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "longPollingEnabled" => polling.enabled = value == "true",
+            "longPollingTimeout" => {
+                polling.timeout = Duration::from_millis(value.parse().unwrap_or_default())
+            }
+            _ => {}
+        }
+    }
+
+    polling
+}
+
+fn read_body<T: DeserializeOwned>(request: &mut Request) -> Result<T, ServerError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| ServerError::of_io(&e.to_string()))?;
+
+    serde_json::from_str(&body).map_err(|e| ServerError::of_bad_request(&e.to_string()))
+}
+
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap()
+}
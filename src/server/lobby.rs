@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::{
+    api_client::{
+        dto::{
+            CellDto, CoordsDto, FieldDto, GameDto, GameSettingsDto, OpeningDto, PlayerDto,
+            ResultDto, SideChoiceDto, StateDto, StatusDto,
+        },
+        mapping::{ToDto, ToEntity},
+    },
+    common::{
+        bot::{Cell, Coords, Field, Side, Turn},
+        error::ServerError,
+        logging,
+        opening::{self, SideChoice, ADDED_STONES, PROPOSED_STONES},
+        rules::{GameSettings, RuleSet},
+    },
+};
+
+pub struct Lobby {
+    settings: GameSettings,
+    queue: Option<String>,
+    players: HashMap<String, Player>,
+    games: Vec<Game>,
+}
+
+struct Player {
+    game: Option<usize>,
+    side: Option<Side>,
+}
+
+struct Game {
+    id: usize,
+    field: Field,
+    state: GameState,
+    current_turn: usize,
+    last_turn: Option<Turn>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GameState {
+    /// Opening rules: X places the opening stones.
+    Opening,
+    /// Opening rules: the player of `Side` takes a side or adds stones.
+    SideChoice(Side),
+    Move(Side),
+    Finished(Option<Side>),
+}
+
+impl Lobby {
+    pub fn new(settings: GameSettings) -> Self {
+        Lobby {
+            settings,
+            queue: None,
+            players: HashMap::new(),
+            games: vec![],
+        }
+    }
+
+    pub fn register(&mut self, name: &str) -> PlayerDto {
+        let id = loop {
+            let id = format!("{:016x}", rand::thread_rng().gen::<u64>());
+            if !self.players.contains_key(&id) {
+                break id;
+            }
+        };
+        logging::info!("player {name:?} registered as {id}");
+
+        self.players.insert(
+            id.clone(),
+            Player {
+                game: None,
+                side: None,
+            },
+        );
+
+        match self.queue.take() {
+            Some(opponent) => self.start_game(&opponent, &id),
+            None => self.queue = Some(id.clone()),
+        }
+
+        self.player(&id).unwrap()
+    }
+
+    pub fn player(&self, id: &str) -> Result<PlayerDto, ServerError> {
+        let player = self
+            .players
+            .get(id)
+            .ok_or_else(|| ServerError::of_unknown_player(id))?;
+
+        Ok(PlayerDto {
+            id: id.to_owned(),
+            status: match player.game {
+                Some(_) => StatusDto::InGame,
+                None => StatusDto::InQueue,
+            },
+            side: player.side.map(|side| side.to_dto()),
+        })
+    }
+
+    pub fn is_in_game(&self, id: &str) -> bool {
+        matches!(self.players.get(id), Some(Player { game: Some(_), .. }))
+    }
+
+    pub fn game(&self, id: &str) -> Result<GameDto, ServerError> {
+        let (_, game) = self.player_game(id)?;
+        Ok(game.to_dto(self.settings))
+    }
+
+    pub fn is_turn_ready(&self, id: &str) -> bool {
+        match self.player_game(id) {
+            Ok((side, game)) => match game.state {
+                GameState::Opening => side == Side::X,
+                GameState::SideChoice(current) | GameState::Move(current) => current == side,
+                GameState::Finished(_) => true,
+            },
+            Err(_) => true,
+        }
+    }
+
+    pub fn make_turn(&mut self, id: &str, coords: CoordsDto) -> Result<GameDto, ServerError> {
+        let (side, _) = self.player_game(id)?;
+        let game_index = self.players[id].game.unwrap();
+        let settings = self.settings;
+        let game = &mut self.games[game_index];
+
+        match game.state {
+            GameState::Move(current) if current == side => {}
+            _ => return Err(ServerError::of_wrong_turn("it's not your turn")),
+        }
+
+        let turn = Turn {
+            coords: coords.to_entity(),
+            side,
+        };
+        let turn = settings
+            .rules
+            .place(&mut game.field, &turn, settings.win_condition)
+            .map_err(|e| ServerError::of_wrong_turn(&e.message))?;
+        game.current_turn += 1;
+        game.last_turn = Some(turn);
+
+        let winner = settings
+            .rules
+            .winner_after(&game.field, &turn, settings.win_condition);
+        game.state = if winner.is_some() {
+            GameState::Finished(winner)
+        } else if settings.rules.is_exhausted(&game.field) {
+            GameState::Finished(None)
+        } else {
+            GameState::Move(side.opposite())
+        };
+
+        Ok(game.to_dto(self.settings))
+    }
+
+    pub fn propose_opening(&mut self, id: &str, dto: OpeningDto) -> Result<GameDto, ServerError> {
+        let (side, game) = self.player_game(id)?;
+        if game.state != GameState::Opening || side != Side::X {
+            return Err(ServerError::of_wrong_turn("it's not your opening"));
+        }
+
+        let stones: Vec<_> = dto.stones.iter().map(|coords| coords.to_entity()).collect();
+        self.place_opening(id, &stones, PROPOSED_STONES)?;
+        let game = &mut self.games[self.players[id].game.unwrap()];
+        game.state = GameState::SideChoice(Side::O);
+
+        Ok(game.to_dto(self.settings))
+    }
+
+    pub fn choose_side(&mut self, id: &str, dto: SideChoiceDto) -> Result<GameDto, ServerError> {
+        let (side, game) = self.player_game(id)?;
+        if game.state != GameState::SideChoice(side) {
+            return Err(ServerError::of_wrong_turn("it's not your choice"));
+        }
+        let game_index = self.players[id].game.unwrap();
+
+        match dto.to_entity() {
+            SideChoice::Take(taken) => {
+                logging::info!("game {game_index}: {id} took {taken:?}");
+                if taken != side {
+                    for player in self.players.values_mut() {
+                        if player.game == Some(game_index) {
+                            player.side = player.side.map(|side| side.opposite());
+                        }
+                    }
+                }
+                let game = &mut self.games[game_index];
+                game.state = GameState::Move(opening::side_to_move(&game.field));
+            }
+            SideChoice::AddStones(stones) => {
+                let opening = self.settings.rules.opening.unwrap();
+                if !opening.can_add_stones(game.current_turn) {
+                    return Err(ServerError::of_wrong_turn("no more stones may be added"));
+                }
+                self.place_opening(id, &stones, ADDED_STONES)?;
+                self.games[game_index].state = GameState::SideChoice(side.opposite());
+            }
+        }
+
+        Ok(self.games[game_index].to_dto(self.settings))
+    }
+
+    fn place_opening(
+        &mut self,
+        id: &str,
+        stones: &[Coords],
+        count: usize,
+    ) -> Result<(), ServerError> {
+        let settings = self.settings;
+        let game = &mut self.games[self.players[id].game.unwrap()];
+        let (rules, win_condition) = (settings.rules, settings.win_condition);
+        let turns = opening::place_stones(&mut game.field, stones, count, rules, win_condition)
+            .map_err(|e| ServerError::of_wrong_turn(&e.message))?;
+        game.current_turn += turns.len();
+        game.last_turn = turns.last().copied();
+
+        Ok(())
+    }
+
+    fn start_game(&mut self, first: &str, second: &str) {
+        let (x, o) = match rand::thread_rng().gen::<bool>() {
+            true => (first, second),
+            false => (second, first),
+        };
+        let id = self.games.len();
+        logging::info!("game {id} started: {x} (X) vs {o} (O)");
+
+        self.games.push(Game {
+            id,
+            field: Field::new(self.settings.field_size),
+            state: match self.settings.rules.opening {
+                Some(_) => GameState::Opening,
+                None => GameState::Move(Side::X),
+            },
+            current_turn: 0,
+            last_turn: None,
+        });
+
+        let game = Some(id);
+        for (id, side) in [(x, Side::X), (o, Side::O)] {
+            let player = self.players.get_mut(id).unwrap();
+            player.game = game;
+            player.side = Some(side);
+        }
+    }
+
+    fn player_game(&self, id: &str) -> Result<(Side, &Game), ServerError> {
+        let player = self
+            .players
+            .get(id)
+            .ok_or_else(|| ServerError::of_unknown_player(id))?;
+
+        match (player.side, player.game) {
+            (Some(side), Some(game)) => Ok((side, &self.games[game])),
+            _ => Err(ServerError::of_no_game(id)),
+        }
+    }
+}
+
+impl Game {
+    fn to_dto(&self, settings: GameSettings) -> GameDto {
+        let size = self.field.size;
+        let cells = (0..size.height)
+            .map(|y| {
+                (0..size.width)
+                    .map(|x| match self.field.geti(x, y) {
+                        Some(Cell::Value(Side::X)) => CellDto::X,
+                        Some(Cell::Value(Side::O)) => CellDto::O,
+                        _ => CellDto::Empty,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        GameDto {
+            id: Some(self.id.to_string()),
+            field: FieldDto {
+                cells,
+                width: size.width,
+                height: size.height,
+                last_move: self.field.last_move().map(|coords| coords.to_dto()),
+            },
+            settings: GameSettingsDto {
+                width: settings.field_size.width,
+                height: settings.field_size.height,
+                win_condition: settings.win_condition,
+                rules: (settings.rules != RuleSet::STANDARD).then(|| settings.rules.to_string()),
+            },
+            state: match self.state {
+                GameState::Move(Side::X) => StateDto::XMove,
+                GameState::Move(Side::O) => StateDto::OMove,
+                GameState::Opening => StateDto::Opening,
+                GameState::SideChoice(Side::X) => StateDto::XSideChoice,
+                GameState::SideChoice(Side::O) => StateDto::OSideChoice,
+                GameState::Finished(_) => StateDto::Finished,
+            },
+            current_turn: self.current_turn,
+            last_turn: self.last_turn.map(|turn| turn.to_dto()),
+            result: match self.state {
+                GameState::Finished(Some(Side::X)) => Some(ResultDto::XWin),
+                GameState::Finished(Some(Side::O)) => Some(ResultDto::OWin),
+                GameState::Finished(None) => Some(ResultDto::Draw),
+                GameState::Opening | GameState::SideChoice(_) | GameState::Move(_) => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::bot::Size;
+
+    use super::*;
+
+    fn settings() -> GameSettings {
+        GameSettings {
+            field_size: Size {
+                width: 3,
+                height: 3,
+            },
+            win_condition: 3,
+            rules: RuleSet::STANDARD,
+        }
+    }
+
+    /// A lobby with a game started, and the ids of its X and O players.
+    fn started_game() -> (Lobby, String, String) {
+        let mut lobby = Lobby::new(settings());
+        let first = lobby.register("first").id;
+        let second = lobby.register("second").id;
+
+        match lobby.players[&first].side {
+            Some(Side::X) => (lobby, first, second),
+            _ => (lobby, second, first),
+        }
+    }
+
+    fn coords(x: isize, y: isize) -> CoordsDto {
+        CoordsDto { x, y }
+    }
+
+    #[test]
+    fn pairs_players_in_order_of_registration() {
+        let mut lobby = Lobby::new(settings());
+
+        let first = lobby.register("first");
+        assert!(matches!(first.status, StatusDto::InQueue));
+        assert!(first.side.is_none());
+        assert!(lobby.game(&first.id).is_err());
+
+        let second = lobby.register("second");
+        let third = lobby.register("third");
+
+        assert!(matches!(second.status, StatusDto::InGame));
+        assert!(lobby.is_in_game(&first.id) && lobby.is_in_game(&second.id));
+        assert!(!lobby.is_in_game(&third.id));
+        let sides = [&first.id, &second.id].map(|id| lobby.players[id].side.unwrap());
+        assert_eq!(sides[0], sides[1].opposite());
+        assert_eq!(
+            lobby.players[&first.id].game,
+            lobby.players[&second.id].game
+        );
+        assert!(lobby.player("unknown").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_side_and_filled_cell() {
+        let (mut lobby, x, o) = started_game();
+        assert!(lobby.is_turn_ready(&x) && !lobby.is_turn_ready(&o));
+
+        assert!(lobby.make_turn(&o, coords(0, 0)).is_err());
+        lobby.make_turn(&x, coords(0, 0)).unwrap();
+        assert!(lobby.make_turn(&x, coords(1, 0)).is_err());
+        assert!(lobby.make_turn(&o, coords(0, 0)).is_err());
+        assert!(lobby.make_turn(&o, coords(3, 0)).is_err());
+
+        let game = lobby.make_turn(&o, coords(1, 1)).unwrap();
+        assert_eq!(game.state, StateDto::XMove);
+        assert_eq!(game.current_turn, 2);
+    }
+
+    #[test]
+    fn finishes_game_on_line_and_full_field() {
+        let (mut lobby, x, o) = started_game();
+        for (id, x, y) in [(&x, 0, 0), (&o, 0, 1), (&x, 1, 0), (&o, 1, 1)] {
+            lobby.make_turn(id, coords(x, y)).unwrap();
+        }
+
+        let game = lobby.make_turn(&x, coords(2, 0)).unwrap();
+
+        assert_eq!(game.state, StateDto::Finished);
+        assert!(matches!(game.result, Some(ResultDto::XWin)));
+        assert!(lobby.is_turn_ready(&o));
+        assert!(lobby.make_turn(&o, coords(2, 1)).is_err());
+
+        let (mut lobby, x, o) = started_game();
+        // X O X / X O O / O X X
+        let turns = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 2),
+            (0, 1),
+            (1, 1),
+            (2, 2),
+            (2, 1),
+            (1, 2),
+        ];
+        let mut game = None;
+        for (turn, (x_coord, y_coord)) in turns.into_iter().enumerate() {
+            let id = if turn % 2 == 0 { &x } else { &o };
+            game = Some(lobby.make_turn(id, coords(x_coord, y_coord)).unwrap());
+        }
+
+        let game = game.unwrap();
+        assert_eq!(game.state, StateDto::Finished);
+        assert!(matches!(game.result, Some(ResultDto::Draw)));
+    }
+}
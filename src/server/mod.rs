@@ -0,0 +1,2 @@
+pub mod game_server;
+pub mod lobby;